@@ -1,9 +1,11 @@
-use criterion::{BenchmarkId, Criterion, Throughput, black_box, criterion_group, criterion_main};
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
 use rand::{RngCore, thread_rng};
+use std::hint::black_box;
 use std::io::Cursor;
 use wave_vortex::{
-    CipherCtx, decrypt_block_ctx, decrypt_stream, derive_key_from_password, encrypt_block_ctx,
-    encrypt_stream,
+    BatchBackend, CipherCtx, decrypt_block_288, decrypt_block_ctx, decrypt_stream,
+    derive_key_from_password, encrypt_block_288, encrypt_block_ctx, encrypt_stream,
+    open_segmented_with_ctx, seal_segmented_with_ctx,
 };
 
 // --- 准备测试数据 ---
@@ -51,6 +53,53 @@ fn bench_block_operations(c: &mut Criterion) {
         b.iter(|| decrypt_block_ctx(black_box(&ctx), black_box(&ciphertext)))
     });
 
+    // 4. 基准测试 288 位等长置换
+    let mut block = [0u8; 36];
+    thread_rng().fill_bytes(&mut block);
+    group.bench_function("Encrypt Block 288 (with context)", |b| {
+        b.iter(|| encrypt_block_288(black_box(&ctx), black_box(&block)))
+    });
+    group.bench_function("Decrypt Block 288 (with context)", |b| {
+        b.iter(|| decrypt_block_288(black_box(&ctx), black_box(&block)))
+    });
+
+    group.finish();
+}
+
+/// 基准测试位切片多块引擎与逐块置换
+fn bench_batch_operations(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Batch Blocks Throughput");
+    let ctx = CipherCtx::new(&gen_32_bytes());
+    let mut blocks = vec![[0u8; 36]; 1024];
+    for block in blocks.iter_mut() {
+        thread_rng().fill_bytes(block);
+    }
+    group.throughput(Throughput::Bytes((blocks.len() * 36) as u64));
+
+    // 可移植实现与本机可用的 SIMD 实现 (需 `--features simd`)
+    for backend in [
+        BatchBackend::Portable,
+        BatchBackend::Sse2,
+        BatchBackend::Avx2,
+        BatchBackend::Neon,
+    ] {
+        if !backend.is_available() {
+            continue;
+        }
+        group.bench_function(format!("Encrypt 1024 Blocks ({:?})", backend), |b| {
+            b.iter(|| backend.encrypt_blocks(black_box(&ctx), black_box(&mut blocks)))
+        });
+        group.bench_function(format!("Decrypt 1024 Blocks ({:?})", backend), |b| {
+            b.iter(|| backend.decrypt_blocks(black_box(&ctx), black_box(&mut blocks)))
+        });
+    }
+    group.bench_function("Encrypt 1024 Blocks (block by block)", |b| {
+        b.iter(|| {
+            for block in blocks.iter_mut() {
+                *block = encrypt_block_288(black_box(&ctx), block);
+            }
+        })
+    });
     group.finish();
 }
 
@@ -104,6 +153,38 @@ fn bench_stream_operations(c: &mut Criterion) {
     group.finish();
 }
 
+/// 基准测试分段模式在不同线程数下的吞吐量
+fn bench_segmented_operations(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Segmented Stream Throughput");
+    let ctx = CipherCtx::new(&gen_32_bytes());
+    let mut data = vec![0u8; 32 * 1024 * 1024];
+    thread_rng().fill_bytes(&mut data);
+    group.throughput(Throughput::Bytes(data.len() as u64));
+    group.sample_size(10);
+
+    let mut sealed = Vec::new();
+    seal_segmented_with_ctx(&mut Cursor::new(&data), &mut sealed, &ctx, b"", 0).unwrap();
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    for threads in [1, cores] {
+        group.bench_with_input(BenchmarkId::new("Seal", threads), &data, |b, data| {
+            b.iter(|| {
+                let mut out = Vec::with_capacity(data.len() + data.len() / 1024);
+                seal_segmented_with_ctx(&mut Cursor::new(data), &mut out, &ctx, b"", threads)
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("Open", threads), &sealed, |b, sealed| {
+            b.iter(|| {
+                let mut out = Vec::with_capacity(sealed.len());
+                open_segmented_with_ctx(&mut Cursor::new(sealed), &mut out, &ctx, b"", threads)
+            })
+        });
+        if cores == 1 {
+            break;
+        }
+    }
+    group.finish();
+}
+
 /// 基准测试密钥派生函数
 fn bench_key_derivation(c: &mut Criterion) {
     let password = b"a-very-strong-and-long-password-for-testing";
@@ -119,7 +200,9 @@ fn bench_key_derivation(c: &mut Criterion) {
 criterion_group!(
     benches,
     bench_block_operations,
+    bench_batch_operations,
     bench_stream_operations,
+    bench_segmented_operations,
     bench_key_derivation
 );
 criterion_main!(benches);
@@ -0,0 +1,408 @@
+// --- Read/Write 适配器：把 CipherCtx 接入现有的 I/O 管道 ---
+//
+// `EncryptWriter` 与 `DecryptReader` 读写的格式与对应的一次性函数完全相同：
+//   CBC:  IV(32) || 密文块...           (encrypt_stream_with_ctx)
+//   EAX:  nonce(24) || 密文 || 标签(32) (seal_stream_with_ctx)
+//   分段: 前缀(19) || 段...             (seal_segmented_with_ctx)
+// 格式逻辑在无 I/O 的 Encoder / Decoder 中，同步与异步适配器只负责搬运字节。
+use crate::aead::{self, EaxSealer, NONCE_SIZE, STREAM_CHUNK_SIZE, TAG_SIZE};
+use crate::segmented::{SegmentOpener, SegmentSealer};
+use crate::{
+    CIPHERTEXT_BLOCK_SIZE, CipherCtx, IV_SIZE, PLAINTEXT_BLOCK_SIZE, SEGMENT_PREFIX_SIZE,
+    StreamMode, decrypt_block_ctx, encrypt_block_ctx, strip_pkcs7,
+};
+use rand::{RngCore, thread_rng};
+use std::io::{Error, ErrorKind, Read, Result as IoResult, Write};
+
+// 解密读取器每次从内部读取器读取的字节数
+pub(crate) const READ_CHUNK_SIZE: usize = 16 * 1024;
+
+// --- 加密核心 ---
+pub(crate) enum Encoder {
+    Cbc(Box<CbcEncoder>),
+    Eax(Box<EaxEncoder>),
+    Segmented(Box<SegmentSealer>),
+}
+impl Encoder {
+    // 以随机 IV/nonce 创建；CBC 忽略 ad
+    pub(crate) fn new(ctx: CipherCtx, mode: StreamMode, ad: &[u8]) -> Self {
+        match mode {
+            StreamMode::Cbc => {
+                let mut iv = [0u8; IV_SIZE];
+                thread_rng().fill_bytes(&mut iv);
+                Encoder::Cbc(Box::new(CbcEncoder {
+                    ctx,
+                    iv_written: false,
+                    prev: iv,
+                    buf: Vec::with_capacity(PLAINTEXT_BLOCK_SIZE),
+                }))
+            }
+            StreamMode::Eax => {
+                let mut nonce = [0u8; NONCE_SIZE];
+                thread_rng().fill_bytes(&mut nonce);
+                Encoder::Eax(Box::new(EaxEncoder {
+                    sealer: EaxSealer::new(&ctx, &nonce, ad),
+                    ctx,
+                    nonce: Some(nonce),
+                    buf: Vec::with_capacity(STREAM_CHUNK_SIZE),
+                }))
+            }
+            StreamMode::Segmented => {
+                let mut prefix = [0u8; SEGMENT_PREFIX_SIZE];
+                thread_rng().fill_bytes(&mut prefix);
+                Encoder::Segmented(Box::new(SegmentSealer::new(ctx, &prefix, ad)))
+            }
+        }
+    }
+    // 消耗 data 的一部分，把产生的密文追加到 out；每次产出的密文有上限
+    pub(crate) fn update(&mut self, data: &[u8], out: &mut Vec<u8>) -> IoResult<usize> {
+        match self {
+            Encoder::Cbc(e) => Ok(e.update(data, out)),
+            Encoder::Eax(e) => Ok(e.update(data, out)),
+            Encoder::Segmented(e) => e.update(data, out),
+        }
+    }
+    // 写出剩余数据及填充/标签/最后一段
+    pub(crate) fn finish(&mut self, out: &mut Vec<u8>) -> IoResult<()> {
+        match self {
+            Encoder::Cbc(e) => e.finish(out),
+            Encoder::Eax(e) => e.finish(out),
+            Encoder::Segmented(e) => return e.finish(out),
+        }
+        Ok(())
+    }
+}
+
+// 整块随写随出；PKCS#7 总会追加填充，所以满块不必等待后续数据
+pub(crate) struct CbcEncoder {
+    ctx: CipherCtx,
+    iv_written: bool,
+    prev: [u8; IV_SIZE],
+    buf: Vec<u8>,
+}
+impl CbcEncoder {
+    fn encrypt_buffered(&mut self, out: &mut Vec<u8>) {
+        if !self.iv_written {
+            out.extend_from_slice(&self.prev);
+            self.iv_written = true;
+        }
+        let mut block = [0u8; PLAINTEXT_BLOCK_SIZE];
+        for ((b, x), p) in block.iter_mut().zip(&self.buf).zip(&self.prev) {
+            *b = x ^ p;
+        }
+        let cipher_block = encrypt_block_ctx(&self.ctx, &block);
+        self.prev
+            .copy_from_slice(&cipher_block[..PLAINTEXT_BLOCK_SIZE]);
+        out.extend_from_slice(&cipher_block);
+        self.buf.clear();
+    }
+    fn update(&mut self, data: &[u8], out: &mut Vec<u8>) -> usize {
+        let data = &data[..data.len().min(STREAM_CHUNK_SIZE)];
+        let mut rest = data;
+        while !rest.is_empty() {
+            let take = (PLAINTEXT_BLOCK_SIZE - self.buf.len()).min(rest.len());
+            self.buf.extend_from_slice(&rest[..take]);
+            rest = &rest[take..];
+            if self.buf.len() == PLAINTEXT_BLOCK_SIZE {
+                self.encrypt_buffered(out);
+            }
+        }
+        data.len()
+    }
+    fn finish(&mut self, out: &mut Vec<u8>) {
+        let padding_val = (PLAINTEXT_BLOCK_SIZE - self.buf.len()) as u8;
+        self.buf.resize(PLAINTEXT_BLOCK_SIZE, padding_val);
+        self.encrypt_buffered(out);
+    }
+}
+
+// 攒满 STREAM_CHUNK_SIZE 再加密，使 CTR 走批量路径
+pub(crate) struct EaxEncoder {
+    ctx: CipherCtx,
+    nonce: Option<[u8; NONCE_SIZE]>,
+    sealer: EaxSealer,
+    buf: Vec<u8>,
+}
+impl EaxEncoder {
+    fn encrypt_buffered(&mut self, out: &mut Vec<u8>) {
+        if let Some(nonce) = self.nonce.take() {
+            out.extend_from_slice(&nonce);
+        }
+        self.sealer.encrypt(&self.ctx, &mut self.buf);
+        out.extend_from_slice(&self.buf);
+        self.buf.clear();
+    }
+    fn update(&mut self, data: &[u8], out: &mut Vec<u8>) -> usize {
+        let n = data.len().min(STREAM_CHUNK_SIZE - self.buf.len());
+        self.buf.extend_from_slice(&data[..n]);
+        if self.buf.len() == STREAM_CHUNK_SIZE {
+            self.encrypt_buffered(out);
+        }
+        n
+    }
+    fn finish(&mut self, out: &mut Vec<u8>) {
+        self.encrypt_buffered(out);
+        out.extend_from_slice(&self.sealer.finalize(&self.ctx));
+    }
+}
+
+// --- 解密核心 ---
+pub(crate) enum Decoder {
+    Cbc(Box<CbcDecoder>),
+    Eax(Box<EaxDecoder>),
+    Segmented(Box<SegmentOpener>),
+}
+impl Decoder {
+    pub(crate) fn new(ctx: CipherCtx, mode: StreamMode, ad: &[u8]) -> Self {
+        match mode {
+            StreamMode::Cbc => Decoder::Cbc(Box::new(CbcDecoder {
+                ctx,
+                prev: None,
+                pending: None,
+                buf: Vec::with_capacity(CIPHERTEXT_BLOCK_SIZE),
+            })),
+            StreamMode::Eax => Decoder::Eax(Box::new(EaxDecoder {
+                ctx,
+                ad: ad.to_vec(),
+                sealed: Vec::new(),
+            })),
+            StreamMode::Segmented => Decoder::Segmented(Box::new(SegmentOpener::new(ctx, ad))),
+        }
+    }
+    // 追加密文；已能确定的明文追加到 out
+    pub(crate) fn update(&mut self, data: &[u8], out: &mut Vec<u8>) -> IoResult<()> {
+        match self {
+            Decoder::Cbc(d) => {
+                d.update(data, out);
+                Ok(())
+            }
+            Decoder::Eax(d) => {
+                d.sealed.extend_from_slice(data);
+                Ok(())
+            }
+            Decoder::Segmented(d) => d.update(data, out),
+        }
+    }
+    // 输入结束：校验末尾并写出剩余明文
+    pub(crate) fn finish(&mut self, out: &mut Vec<u8>) -> IoResult<()> {
+        match self {
+            Decoder::Cbc(d) => d.finish(out),
+            Decoder::Eax(d) => d.finish(out),
+            Decoder::Segmented(d) => d.finish(out),
+        }
+    }
+}
+
+// 最后一块要等输入结束才能确定并去掉填充，所以总是压住一块明文
+pub(crate) struct CbcDecoder {
+    ctx: CipherCtx,
+    prev: Option<[u8; IV_SIZE]>,
+    pending: Option<[u8; PLAINTEXT_BLOCK_SIZE]>,
+    buf: Vec<u8>,
+}
+impl CbcDecoder {
+    fn update(&mut self, mut data: &[u8], out: &mut Vec<u8>) {
+        while !data.is_empty() {
+            let want = match self.prev {
+                None => IV_SIZE,
+                Some(_) => CIPHERTEXT_BLOCK_SIZE,
+            };
+            let take = (want - self.buf.len()).min(data.len());
+            self.buf.extend_from_slice(&data[..take]);
+            data = &data[take..];
+            if self.buf.len() < want {
+                break;
+            }
+            match &mut self.prev {
+                None => self.prev = Some(self.buf[..].try_into().unwrap()),
+                Some(prev) => {
+                    let block: &[u8; CIPHERTEXT_BLOCK_SIZE] = self.buf[..].try_into().unwrap();
+                    let mut pt = decrypt_block_ctx(&self.ctx, block);
+                    for i in 0..PLAINTEXT_BLOCK_SIZE {
+                        pt[i] ^= prev[i];
+                    }
+                    prev.copy_from_slice(&block[..IV_SIZE]);
+                    if let Some(ready) = self.pending.replace(pt) {
+                        out.extend_from_slice(&ready);
+                    }
+                }
+            }
+            self.buf.clear();
+        }
+    }
+    fn finish(&mut self, out: &mut Vec<u8>) -> IoResult<()> {
+        if self.prev.is_none() || !self.buf.is_empty() {
+            return Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                "CBC ciphertext ends with a partial block.",
+            ));
+        }
+        let last = self
+            .pending
+            .take()
+            .ok_or_else(|| Error::new(ErrorKind::UnexpectedEof, "CBC ciphertext has no blocks."))?;
+        out.extend_from_slice(&last[..strip_pkcs7(&last)?]);
+        Ok(())
+    }
+}
+
+// EAX 只有一个标签，必须读完全部密文并校验后才能交出明文
+pub(crate) struct EaxDecoder {
+    ctx: CipherCtx,
+    ad: Vec<u8>,
+    sealed: Vec<u8>,
+}
+impl EaxDecoder {
+    fn finish(&mut self, out: &mut Vec<u8>) -> IoResult<()> {
+        if self.sealed.len() < NONCE_SIZE + TAG_SIZE {
+            return Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                "Ciphertext is shorter than the authentication tag.",
+            ));
+        }
+        let (nonce, sealed) = self.sealed.split_at(NONCE_SIZE);
+        let opened = aead::open(&self.ctx, nonce.try_into().unwrap(), &self.ad, sealed)?;
+        out.extend_from_slice(&opened);
+        self.sealed = Vec::new();
+        Ok(())
+    }
+}
+
+/// 加密写入器：写入明文，向内部写入器输出密文。
+///
+/// 必须调用 [`finish`](Self::finish) 写出填充块 (CBC)、标签 (EAX) 或最后一段 (分段)；
+/// 直接丢弃得到的是不完整的密文。
+pub struct EncryptWriter<W: Write> {
+    inner: W,
+    encoder: Encoder,
+    out: Vec<u8>,
+}
+impl<W: Write> EncryptWriter<W> {
+    /// 以随机 IV/nonce 创建，关联数据为空。
+    pub fn new(inner: W, ctx: CipherCtx, mode: StreamMode) -> Self {
+        Self::with_ad(inner, ctx, mode, b"")
+    }
+    /// 以随机 IV/nonce 创建并认证关联数据 `ad`；CBC 无认证，忽略 `ad`。
+    pub fn with_ad(inner: W, ctx: CipherCtx, mode: StreamMode, ad: &[u8]) -> Self {
+        ctx.assert_full_strength();
+        Self::from_encoder(inner, Encoder::new(ctx, mode, ad))
+    }
+    pub(crate) fn from_encoder(inner: W, encoder: Encoder) -> Self {
+        EncryptWriter {
+            inner,
+            encoder,
+            out: Vec::new(),
+        }
+    }
+    fn write_out(&mut self) -> IoResult<()> {
+        self.inner.write_all(&self.out)?;
+        self.out.clear();
+        Ok(())
+    }
+    /// 写出剩余数据及填充/标签，刷新并返回内部写入器。
+    pub fn finish(mut self) -> IoResult<W> {
+        self.write_out()?;
+        self.encoder.finish(&mut self.out)?;
+        self.write_out()?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+impl<W: Write> Write for EncryptWriter<W> {
+    fn write(&mut self, data: &[u8]) -> IoResult<usize> {
+        self.write_out()?;
+        let n = self.encoder.update(data, &mut self.out)?;
+        self.write_out()?;
+        Ok(n)
+    }
+    /// 只刷新内部写入器；缓冲中不足一块 (或一段) 的明文要到 `finish` 时才写出。
+    fn flush(&mut self) -> IoResult<()> {
+        self.write_out()?;
+        self.inner.flush()
+    }
+}
+
+/// 解密读取器：从内部读取器读密文，交出明文。
+///
+/// 分段模式逐段校验后交出明文；EAX 只有一个标签，会先读完全部密文并校验，
+/// 之后才交出明文；CBC 无认证，但末尾出现不完整的块或缺少填充块时返回错误。
+/// 出错后的每次 `read` 都返回错误，不会表现为正常结束。
+pub struct DecryptReader<R: Read> {
+    inner: R,
+    decoder: Decoder,
+    plain: Vec<u8>,
+    pos: usize,
+    state: ReadState,
+}
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ReadState {
+    Reading,
+    Finished,
+    Failed,
+}
+impl<R: Read> DecryptReader<R> {
+    /// 关联数据为空。
+    pub fn new(inner: R, ctx: CipherCtx, mode: StreamMode) -> Self {
+        Self::with_ad(inner, ctx, mode, b"")
+    }
+    /// 校验关联数据 `ad`；CBC 无认证，忽略 `ad`。
+    pub fn with_ad(inner: R, ctx: CipherCtx, mode: StreamMode, ad: &[u8]) -> Self {
+        ctx.assert_full_strength();
+        DecryptReader {
+            inner,
+            decoder: Decoder::new(ctx, mode, ad),
+            plain: Vec::new(),
+            pos: 0,
+            state: ReadState::Reading,
+        }
+    }
+    /// 返回内部读取器。
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+    fn fill(&mut self) -> IoResult<()> {
+        let mut chunk = [0u8; READ_CHUNK_SIZE];
+        let n = loop {
+            match self.inner.read(&mut chunk) {
+                Ok(n) => break n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        };
+        if n == 0 {
+            self.state = ReadState::Finished;
+            self.decoder.finish(&mut self.plain)
+        } else {
+            self.decoder.update(&chunk[..n], &mut self.plain)
+        }
+    }
+}
+impl<R: Read> Read for DecryptReader<R> {
+    fn read(&mut self, out: &mut [u8]) -> IoResult<usize> {
+        while self.pos == self.plain.len() {
+            match self.state {
+                ReadState::Failed => return Err(failed_error()),
+                ReadState::Finished => return Ok(0),
+                ReadState::Reading if out.is_empty() => return Ok(0),
+                ReadState::Reading => {}
+            }
+            self.plain.clear();
+            self.pos = 0;
+            if let Err(e) = self.fill() {
+                self.state = ReadState::Failed;
+                self.plain.clear();
+                return Err(e);
+            }
+        }
+        let n = out.len().min(self.plain.len() - self.pos);
+        out[..n].copy_from_slice(&self.plain[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+pub(crate) fn failed_error() -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        "Decryption already failed; the stream cannot be resumed.",
+    )
+}
@@ -0,0 +1,226 @@
+// --- EAX 认证加密 (CTR + OMAC，先加密后认证) ---
+use crate::ctr::Ctr;
+use crate::mac::Cmac;
+use crate::{BLOCK_SIZE, CipherCtx, read_full};
+use rand::{RngCore, thread_rng};
+use std::fmt;
+use std::io::{Read, Result as IoResult, Write};
+
+pub const NONCE_SIZE: usize = 24;
+pub const TAG_SIZE: usize = 32;
+// OMAC 域分离标签: N' = OMAC^0(N), H = OMAC^1(AD), C' = OMAC^2(C)；
+// 3 和 4 由 SIV 使用
+const TWEAK_NONCE: u8 = 0;
+const TWEAK_AD: u8 = 1;
+const TWEAK_CT: u8 = 2;
+// 流式加密时每次处理的字节数，为整块的倍数
+pub(crate) const STREAM_CHUNK_SIZE: usize = BLOCK_SIZE * 1024;
+
+/// 认证失败：密文、标签、nonce 或关联数据被篡改，或密钥错误。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuthError;
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("authentication failed: ciphertext or associated data was modified")
+    }
+}
+impl std::error::Error for AuthError {}
+impl From<AuthError> for std::io::Error {
+    fn from(e: AuthError) -> Self {
+        std::io::Error::other(e)
+    }
+}
+
+pub(crate) fn omac_start(ctx: &CipherCtx, tweak: u8) -> Cmac {
+    let mut t = [0u8; BLOCK_SIZE];
+    t[0] = tweak;
+    let mut mac = Cmac::new(ctx);
+    mac.update(ctx, &t);
+    mac
+}
+pub(crate) fn omac(ctx: &CipherCtx, tweak: u8, data: &[u8]) -> [u8; BLOCK_SIZE] {
+    let mut mac = omac_start(ctx, tweak);
+    mac.update(ctx, data);
+    mac.finalize(ctx)
+}
+fn make_tag(n: &[u8; BLOCK_SIZE], h: &[u8; BLOCK_SIZE], c: &[u8; BLOCK_SIZE]) -> [u8; TAG_SIZE] {
+    let mut tag = [0u8; TAG_SIZE];
+    for i in 0..TAG_SIZE {
+        tag[i] = n[i] ^ h[i] ^ c[i];
+    }
+    tag
+}
+// 常数时间比较，长度不同直接判为不等 (长度不是秘密)
+pub(crate) fn tags_equal(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut diff = 0u8;
+    for (x, y) in a.iter().zip(b) {
+        diff |= x ^ y;
+    }
+    diff == 0
+}
+
+/// 原地加密 `buffer`，返回认证标签。
+pub fn seal_in_place_detached(
+    ctx: &CipherCtx,
+    nonce: &[u8; NONCE_SIZE],
+    ad: &[u8],
+    buffer: &mut [u8],
+) -> [u8; TAG_SIZE] {
+    ctx.assert_full_strength();
+    let n = omac(ctx, TWEAK_NONCE, nonce);
+    let h = omac(ctx, TWEAK_AD, ad);
+    Ctr::from_initial_block(ctx, n).apply_keystream(buffer);
+    let c = omac(ctx, TWEAK_CT, buffer);
+    make_tag(&n, &h, &c)
+}
+/// 校验标签后原地解密 `buffer`；校验失败时 `buffer` 保持为密文。
+pub fn open_in_place_detached(
+    ctx: &CipherCtx,
+    nonce: &[u8; NONCE_SIZE],
+    ad: &[u8],
+    buffer: &mut [u8],
+    tag: &[u8; TAG_SIZE],
+) -> Result<(), AuthError> {
+    ctx.assert_full_strength();
+    let n = omac(ctx, TWEAK_NONCE, nonce);
+    let h = omac(ctx, TWEAK_AD, ad);
+    let c = omac(ctx, TWEAK_CT, buffer);
+    if !tags_equal(&make_tag(&n, &h, &c), tag) {
+        return Err(AuthError);
+    }
+    Ctr::from_initial_block(ctx, n).apply_keystream(buffer);
+    Ok(())
+}
+/// 加密并认证，输出 `密文 || 标签`。
+pub fn seal(ctx: &CipherCtx, nonce: &[u8; NONCE_SIZE], ad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(plaintext.len() + TAG_SIZE);
+    out.extend_from_slice(plaintext);
+    let tag = seal_in_place_detached(ctx, nonce, ad, &mut out);
+    out.extend_from_slice(&tag);
+    out
+}
+/// 校验并解密 `seal` 的输出。
+pub fn open(
+    ctx: &CipherCtx,
+    nonce: &[u8; NONCE_SIZE],
+    ad: &[u8],
+    sealed: &[u8],
+) -> Result<Vec<u8>, AuthError> {
+    if sealed.len() < TAG_SIZE {
+        return Err(AuthError);
+    }
+    let (ct, tag) = sealed.split_at(sealed.len() - TAG_SIZE);
+    let mut out = ct.to_vec();
+    open_in_place_detached(ctx, nonce, ad, &mut out, tag.try_into().unwrap())?;
+    Ok(out)
+}
+
+// --- 流式 API: nonce(24) || 密文 || 标签(32) ---
+// 增量 EAX 加密状态，不借用 CipherCtx；每次调用重建 CTR，按整块的倍数分批处理最快
+pub(crate) struct EaxSealer {
+    n: [u8; BLOCK_SIZE],
+    h: [u8; BLOCK_SIZE],
+    c_mac: Cmac,
+    pos: u64,
+}
+impl EaxSealer {
+    pub(crate) fn new(ctx: &CipherCtx, nonce: &[u8; NONCE_SIZE], ad: &[u8]) -> Self {
+        Self {
+            n: omac(ctx, TWEAK_NONCE, nonce),
+            h: omac(ctx, TWEAK_AD, ad),
+            c_mac: omac_start(ctx, TWEAK_CT),
+            pos: 0,
+        }
+    }
+    pub(crate) fn encrypt(&mut self, ctx: &CipherCtx, chunk: &mut [u8]) {
+        let mut ctr = Ctr::from_initial_block(ctx, self.n);
+        ctr.seek(self.pos);
+        ctr.apply_keystream(chunk);
+        self.pos += chunk.len() as u64;
+        self.c_mac.update(ctx, chunk);
+    }
+    pub(crate) fn finalize(&self, ctx: &CipherCtx) -> [u8; TAG_SIZE] {
+        make_tag(&self.n, &self.h, &self.c_mac.clone().finalize(ctx))
+    }
+}
+
+/// 以随机 nonce 流式加密，末尾追加标签。
+pub fn seal_stream_with_ctx(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    ctx: &CipherCtx,
+    ad: &[u8],
+) -> IoResult<()> {
+    let mut nonce = [0u8; NONCE_SIZE];
+    thread_rng().fill_bytes(&mut nonce);
+    seal_stream_with_nonce(reader, writer, ctx, &nonce, ad)
+}
+/// 与 [`seal_stream_with_ctx`] 相同，但使用调用者给定的 nonce。
+///
+/// 同一密钥下 nonce 绝不能重复使用。
+pub fn seal_stream_with_nonce(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    ctx: &CipherCtx,
+    nonce: &[u8; NONCE_SIZE],
+    ad: &[u8],
+) -> IoResult<()> {
+    ctx.assert_full_strength();
+    writer.write_all(nonce)?;
+    seal_stream_body(reader, writer, ctx, nonce, ad)
+}
+pub(crate) fn seal_stream_body(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    ctx: &CipherCtx,
+    nonce: &[u8; NONCE_SIZE],
+    ad: &[u8],
+) -> IoResult<()> {
+    let mut sealer = EaxSealer::new(ctx, nonce, ad);
+    let mut buffer = vec![0u8; STREAM_CHUNK_SIZE];
+    loop {
+        let bytes_read = read_full(reader, &mut buffer)?;
+        if bytes_read == 0 {
+            break;
+        }
+        let chunk = &mut buffer[..bytes_read];
+        sealer.encrypt(ctx, chunk);
+        writer.write_all(chunk)?;
+    }
+    writer.write_all(&sealer.finalize(ctx))
+}
+/// 读取完整输入并校验标签，通过后才写出明文。
+pub fn open_stream_with_ctx(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    ctx: &CipherCtx,
+    ad: &[u8],
+) -> IoResult<()> {
+    ctx.assert_full_strength();
+    let mut nonce = [0u8; NONCE_SIZE];
+    reader.read_exact(&mut nonce)?;
+    open_stream_body(reader, writer, ctx, &nonce, ad)
+}
+pub(crate) fn open_stream_body(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    ctx: &CipherCtx,
+    nonce: &[u8; NONCE_SIZE],
+    ad: &[u8],
+) -> IoResult<()> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    if data.len() < TAG_SIZE {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            "Ciphertext is shorter than the authentication tag.",
+        ));
+    }
+    let tag: [u8; TAG_SIZE] = data[data.len() - TAG_SIZE..].try_into().unwrap();
+    data.truncate(data.len() - TAG_SIZE);
+    open_in_place_detached(ctx, nonce, ad, &mut data, &tag)?;
+    writer.write_all(&data)
+}
@@ -1,15 +1,26 @@
 use statrs::statistics::{Data, Distribution};
-use wave_vortex::{SBOX, encrypt_block};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use wave_vortex::{
+    CipherCtx, Layers, LinearSearch, ROUNDS, SBOX, differential_model, encrypt_block,
+    encrypt_block_ctx, linear_model, mds_branch_number, mds_linear_branch_number,
+    search_linear_trail,
+};
+
+// 雪崩测试、特征模型和线性特征搜索默认使用的密钥
+pub const ANALYSIS_KEY: [u8; 32] = [0x42; 32];
 
 // --- 分析与测试函数 ---
 
 // 计算DDT
+#[allow(clippy::needless_range_loop)]
 fn compute_ddt(sbox: &[u16; 512]) -> (f64, u32) {
     let mut ddt = vec![vec![0u32; 512]; 512];
     for x in 0..512 {
         for delta in 1..512 {
             let xp = x ^ delta;
-            let out_delta = (sbox[x as usize] ^ sbox[xp as usize]) as usize;
+            let out_delta = (sbox[x] ^ sbox[xp]) as usize;
             ddt[delta][out_delta] += 1;
         }
     }
@@ -26,7 +37,7 @@ fn compute_lat(sbox: &[u16; 512]) -> f64 {
             let mut w: i32 = 0;
             for x in 0..512 {
                 let in_parity = ((a as u16 & x as u16).count_ones() & 1) as i32;
-                let out_parity = ((b as u16 & sbox[x as usize] as u16).count_ones() & 1) as i32;
+                let out_parity = ((b as u16 & sbox[x as usize]).count_ones() & 1) as i32;
                 w += if in_parity == out_parity { 1 } else { -1 };
             }
             max_abs_w = max_abs_w.max(w.abs());
@@ -52,11 +63,14 @@ fn bytes_to_hex(bytes: &[u8]) -> String {
         .join(" ")
 }
 
+// 零距离案例: (翻转位, 原明文, 翻转明文, 原密文, 翻转密文)
+type ZeroCase = (usize, String, String, String, String);
+
 // 雪崩测试单消息
 fn run_avalanche_for_message(
     msg: &[u8; 32],
     encrypt_fn: impl Fn(&[u8; 32]) -> [u8; 36],
-) -> (Vec<u32>, Vec<(usize, String, String, String, String)>) {
+) -> (Vec<u32>, Vec<ZeroCase>) {
     let h_base = encrypt_fn(msg);
     let base_hex = bytes_to_hex(&h_base);
     let pt_hex = bytes_to_hex(msg);
@@ -100,6 +114,75 @@ fn calculate_stats(dists: &[u32]) -> (f64, f64, u32, u32) {
     let max = *dists.iter().max().unwrap_or(&0);
     (mean, stdev, min, max)
 }
+
+// 与 SAC 检查相同的判据：均值接近 144 且没有零距离
+fn is_full_diffusion(mean: f64, min: u32) -> bool {
+    (mean - 144.0).abs() < 5.0 && min > 0
+}
+
+// 缩减轮雪崩：完整轮函数及逐一去掉某层时，每个轮数下的平均汉明距离
+fn run_reduced_rounds(messages: &[[u8; 32]], key: &[u8; 32]) {
+    let configs = [
+        ("full", Layers::ALL),
+        (
+            "-MDS",
+            Layers {
+                mds: false,
+                ..Layers::ALL
+            },
+        ),
+        (
+            "-BitRotate",
+            Layers {
+                bit_rotate: false,
+                ..Layers::ALL
+            },
+        ),
+        (
+            "-StreamFwd",
+            Layers {
+                stream_fwd: false,
+                ..Layers::ALL
+            },
+        ),
+        (
+            "-VtxShuffle",
+            Layers {
+                vtx_shuffle: false,
+                ..Layers::ALL
+            },
+        ),
+    ];
+    print!("{:>6}", "Rounds");
+    for (name, _) in &configs {
+        print!(" {:>12}", name);
+    }
+    println!();
+    let mut reached = [None; 5];
+    for n in 1..=ROUNDS {
+        print!("{:>6}", n);
+        for (i, (_, layers)) in configs.iter().enumerate() {
+            let ctx = CipherCtx::with_rounds(key, n).with_layers(*layers);
+            let dists: Vec<u32> = messages
+                .iter()
+                .flat_map(|msg| run_avalanche_for_message(msg, |pt| encrypt_block_ctx(&ctx, pt)).0)
+                .collect();
+            let (mean, _, min, _) = calculate_stats(&dists);
+            if reached[i].is_none() && is_full_diffusion(mean, min) {
+                reached[i] = Some(n);
+            }
+            print!(" {:>12.2}", mean);
+        }
+        println!();
+    }
+    println!("Rounds to full diffusion:");
+    for ((name, _), n) in configs.iter().zip(reached) {
+        match n {
+            Some(n) => println!("  {:<12} {}", name, n),
+            None => println!("  {:<12} not reached in {} rounds", name, ROUNDS),
+        }
+    }
+}
 pub fn run() {
     // S-box分析
     // 注意：我们直接从库中引用 SBOX 常量
@@ -111,7 +194,12 @@ pub fn run() {
         max_dp, max_count
     );
     let max_bias = compute_lat(sbox);
-    println!("Max Bias (Linear Approximation) = {:.4}\n", max_bias);
+    println!("Max Bias (Linear Approximation) = {:.4}", max_bias);
+    println!(
+        "MDS branch number = {} (differential), {} (linear)\n",
+        mds_branch_number(),
+        mds_linear_branch_number()
+    );
 
     // 雪崩测试
     println!("--- Avalanche Test ---");
@@ -127,7 +215,7 @@ pub fn run() {
         *b"Wave-Vortex Avalanche Test Suite",
     ];
 
-    let test_key = [0x42; 32];
+    let test_key = ANALYSIS_KEY;
     let mut all_dists = Vec::new();
     let mut all_zero_cases = Vec::new();
 
@@ -158,6 +246,10 @@ pub fn run() {
         println!();
     }
 
+    println!("--- Reduced-Round Avalanche (mean distance) ---");
+    run_reduced_rounds(&messages, &test_key);
+    println!();
+
     // 验证SAC (输出288位，期望均值为144)
     println!("--- Strict Avalanche Criterion (SAC) Validation ---");
     let expected_mean = (36 * 8) as f64 / 2.0;
@@ -190,3 +282,170 @@ pub fn run() {
         }
     }
 }
+
+// 差分/线性特征模型：每个轮数各写出一个 LP 文件，由外部 MILP 求解器求最少活跃 S 盒数 A，
+// 该密钥下任一差分特征的概率不超过 (max DP)^A，任一线性特征的相关度不超过 (max |c|)^A
+pub fn write_trail_models(dir: &Path, key: &[u8; 32], max_rounds: usize) -> io::Result<()> {
+    println!("--- Differential and Linear Trail Models ---");
+    let (_, max_count) = compute_ddt(&SBOX);
+    let dp_weight = -(max_count as f64 / 512.0).log2();
+    let corr_weight = -compute_lat(&SBOX).log2();
+    println!(
+        "{:>6} {:>12} {:>10} {:>12}  File",
+        "Rounds", "Model", "Variables", "Constraints"
+    );
+    for n in 1..=max_rounds {
+        let ctx = CipherCtx::with_rounds(key, n);
+        for (kind, prefix, model) in [
+            ("differential", "diff", differential_model(&ctx)),
+            ("linear", "lin", linear_model(&ctx)),
+        ] {
+            let path = dir.join(format!("wvx_{prefix}_r{n}.lp"));
+            let mut out = BufWriter::new(File::create(&path)?);
+            model.write_lp(&mut out)?;
+            out.flush()?;
+            println!(
+                "{:>6} {:>12} {:>10} {:>12}  {}",
+                n,
+                kind,
+                model.num_variables(),
+                model.num_constraints(),
+                path.display()
+            );
+        }
+    }
+    println!("Solve with any MILP solver, e.g. `highs <file>` or `glpsol --lp <file>`.");
+    println!(
+        "An optimum of A active S-boxes bounds every differential trail by 2^-({:.2} * A)",
+        dp_weight
+    );
+    println!(
+        "and every linear trail correlation by 2^-({:.2} * A).",
+        corr_weight
+    );
+    Ok(())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+// 线性特征搜索：对 1..=max_rounds 轮各搜索一次，打印摘要并写出 JSON 报告，
+// 便于在修改 S 盒、MDS 或流动置换后比较结果
+pub fn write_linear_report(path: &Path, key: &[u8; 32], max_rounds: usize) -> io::Result<()> {
+    println!("--- Linear Trail Search ---");
+    let opts = LinearSearch::default();
+    let layers = Layers::ALL;
+    let mut out = BufWriter::new(File::create(path)?);
+    writeln!(out, "{{")?;
+    writeln!(out, "  \"key\": \"{}\",", hex(key))?;
+    writeln!(
+        out,
+        "  \"layers\": {{\"mds\": {}, \"bit_rotate\": {}, \"stream_fwd\": {}, \"vtx_shuffle\": {}}},",
+        layers.mds, layers.bit_rotate, layers.stream_fwd, layers.vtx_shuffle
+    )?;
+    let max_corr = compute_lat(&SBOX);
+    writeln!(out, "  \"sbox_max_abs_correlation\": {:e},", max_corr)?;
+    writeln!(
+        out,
+        "  \"mds_linear_branch_number\": {},",
+        mds_linear_branch_number()
+    )?;
+    writeln!(
+        out,
+        "  \"search\": {{\"start_cells\": [0], \"candidates_per_sbox\": {}, \"max_nodes\": {}}},",
+        opts.candidates, opts.max_nodes
+    )?;
+    writeln!(
+        out,
+        "  \"note\": \"Each trail is a concrete trail found under the search restrictions: its correlation is a lower bound on the best trail correlation, not the maximum. model_bound is the upper bound from the linear trail model (wvx_lin_rN.lp from --trail-models) with one active S-box per round; solving the model can only raise active_sboxes_at_least.\","
+    )?;
+    writeln!(out, "  \"results\": [")?;
+    println!(
+        "{:>6} {:>10} {:>10} {:>8} {:>10}  Exhaustive within limits",
+        "Rounds", "log2|c| >=", "log2|c| <=", "Active", "Nodes"
+    );
+    for n in 1..=max_rounds {
+        let ctx = CipherCtx::with_rounds(key, n).with_layers(layers);
+        // 线性层可逆，每轮至少一个活跃 S 盒
+        let bound_active = n;
+        let bound_log2 = bound_active as f64 * max_corr.log2();
+        // 找不到特征的轮数也写一项，保证每个轮数都出现、分隔符正确
+        let sep = if n < max_rounds { "," } else { "" };
+        writeln!(out, "    {{")?;
+        writeln!(out, "      \"rounds\": {},", n)?;
+        writeln!(
+            out,
+            "      \"model_bound\": {{\"model\": \"wvx_lin_r{}.lp\", \"active_sboxes_at_least\": {}, \"best_trail_log2_correlation_at_most\": {:.4}}},",
+            n, bound_active, bound_log2
+        )?;
+        let Some(trail) = search_linear_trail(&ctx, &opts) else {
+            println!("{:>6}  no trail within {} nodes", n, opts.max_nodes);
+            writeln!(out, "      \"trail\": null")?;
+            writeln!(out, "    }}{}", sep)?;
+            continue;
+        };
+        println!(
+            "{:>6} {:>10.2} {:>10.2} {:>8} {:>10}  {}",
+            n,
+            trail.log2_correlation(),
+            bound_log2,
+            trail.active_sboxes(),
+            trail.nodes,
+            trail.exhaustive_within_limits
+        );
+        writeln!(
+            out,
+            "      \"log2_correlation\": {:.4},",
+            trail.log2_correlation()
+        )?;
+        writeln!(out, "      \"correlation\": {:e},", trail.correlation())?;
+        writeln!(out, "      \"active_sboxes\": {},", trail.active_sboxes())?;
+        writeln!(out, "      \"nodes\": {},", trail.nodes)?;
+        writeln!(
+            out,
+            "      \"exhaustive_within_limits\": {},",
+            trail.exhaustive_within_limits
+        )?;
+        writeln!(
+            out,
+            "      \"best_trail_log2_correlation_at_least\": {:.4},",
+            trail.log2_correlation()
+        )?;
+        writeln!(
+            out,
+            "      \"input_mask\": \"{}\",",
+            hex(&trail.rounds[0].input)
+        )?;
+        writeln!(out, "      \"output_mask\": \"{}\",", hex(&trail.output))?;
+        writeln!(out, "      \"trail\": [")?;
+        for (r, round) in trail.rounds.iter().enumerate() {
+            let sboxes: Vec<String> = round
+                .sboxes
+                .iter()
+                .map(|s| {
+                    format!(
+                        "{{\"cell\": {}, \"input\": \"{:03x}\", \"output\": \"{:03x}\", \"correlation\": {:e}}}",
+                        s.cell, s.input, s.output, s.correlation
+                    )
+                })
+                .collect();
+            let sep = if r + 1 < trail.rounds.len() { "," } else { "" };
+            writeln!(
+                out,
+                "        {{\"round\": {}, \"input_mask\": \"{}\", \"sboxes\": [{}]}}{}",
+                r + 1,
+                hex(&round.input),
+                sboxes.join(", "),
+                sep
+            )?;
+        }
+        writeln!(out, "      ]")?;
+        writeln!(out, "    }}{}", sep)?;
+    }
+    writeln!(out, "  ]")?;
+    writeln!(out, "}}")?;
+    out.flush()?;
+    println!("Linear trail report written to {}", path.display());
+    Ok(())
+}
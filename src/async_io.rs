@@ -0,0 +1,161 @@
+// --- 异步适配器 (`async` 特性) ---
+//
+// 与同步的 EncryptWriter / DecryptReader 共用 Encoder / Decoder，
+// 因此三种格式都与同步版本逐字节一致；这里只负责在 poll 中搬运字节。
+use crate::adapters::{Decoder, Encoder, READ_CHUNK_SIZE, ReadState, failed_error};
+use crate::{CipherCtx, StreamMode};
+use std::io::{Error, ErrorKind, Result as IoResult};
+use std::pin::Pin;
+use std::task::{Context, Poll, ready};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// 异步加密写入器，格式与 [`EncryptWriter`](crate::EncryptWriter) 相同。
+///
+/// 必须调用 [`finish`](Self::finish) 或 `shutdown()` 写出填充/标签/最后一段；
+/// `shutdown()` 还会关闭内部写入器。
+pub struct AsyncEncryptWriter<W> {
+    inner: W,
+    encoder: Encoder,
+    out: Vec<u8>,
+    pos: usize,
+    finished: bool,
+}
+impl<W: AsyncWrite + Unpin> AsyncEncryptWriter<W> {
+    /// 以随机 IV/nonce 创建，关联数据为空。
+    pub fn new(inner: W, ctx: CipherCtx, mode: StreamMode) -> Self {
+        Self::with_ad(inner, ctx, mode, b"")
+    }
+    /// 以随机 IV/nonce 创建并认证关联数据 `ad`；CBC 无认证，忽略 `ad`。
+    pub fn with_ad(inner: W, ctx: CipherCtx, mode: StreamMode, ad: &[u8]) -> Self {
+        ctx.assert_full_strength();
+        AsyncEncryptWriter {
+            inner,
+            encoder: Encoder::new(ctx, mode, ad),
+            out: Vec::new(),
+            pos: 0,
+            finished: false,
+        }
+    }
+    /// 写出剩余数据及填充/标签，刷新 (但不关闭) 并返回内部写入器。
+    pub async fn finish(mut self) -> IoResult<W> {
+        std::future::poll_fn(|cx| self.poll_finish(cx)).await?;
+        Ok(self.inner)
+    }
+    fn poll_write_out(&mut self, cx: &mut Context<'_>) -> Poll<IoResult<()>> {
+        while self.pos < self.out.len() {
+            let n = ready!(Pin::new(&mut self.inner).poll_write(cx, &self.out[self.pos..]))?;
+            if n == 0 {
+                return Poll::Ready(Err(ErrorKind::WriteZero.into()));
+            }
+            self.pos += n;
+        }
+        self.out.clear();
+        self.pos = 0;
+        Poll::Ready(Ok(()))
+    }
+    fn poll_finish(&mut self, cx: &mut Context<'_>) -> Poll<IoResult<()>> {
+        ready!(self.poll_write_out(cx))?;
+        if !self.finished {
+            self.encoder.finish(&mut self.out)?;
+            self.finished = true;
+            ready!(self.poll_write_out(cx))?;
+        }
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+}
+impl<W: AsyncWrite + Unpin> AsyncWrite for AsyncEncryptWriter<W> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        data: &[u8],
+    ) -> Poll<IoResult<usize>> {
+        let this = self.get_mut();
+        ready!(this.poll_write_out(cx))?;
+        if this.finished {
+            return Poll::Ready(Err(Error::other("write after the stream was finished")));
+        }
+        // 产生的密文留在缓冲区，下一次 poll 时写出
+        Poll::Ready(this.encoder.update(data, &mut this.out))
+    }
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<IoResult<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_write_out(cx))?;
+        Pin::new(&mut this.inner).poll_flush(cx)
+    }
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<IoResult<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_finish(cx))?;
+        Pin::new(&mut this.inner).poll_shutdown(cx)
+    }
+}
+
+/// 异步解密读取器，格式与校验规则与 [`DecryptReader`](crate::DecryptReader) 相同。
+pub struct AsyncDecryptReader<R> {
+    inner: R,
+    decoder: Decoder,
+    chunk: Vec<u8>,
+    plain: Vec<u8>,
+    pos: usize,
+    state: ReadState,
+}
+impl<R: AsyncRead + Unpin> AsyncDecryptReader<R> {
+    /// 关联数据为空。
+    pub fn new(inner: R, ctx: CipherCtx, mode: StreamMode) -> Self {
+        Self::with_ad(inner, ctx, mode, b"")
+    }
+    /// 校验关联数据 `ad`；CBC 无认证，忽略 `ad`。
+    pub fn with_ad(inner: R, ctx: CipherCtx, mode: StreamMode, ad: &[u8]) -> Self {
+        ctx.assert_full_strength();
+        AsyncDecryptReader {
+            inner,
+            decoder: Decoder::new(ctx, mode, ad),
+            chunk: vec![0u8; READ_CHUNK_SIZE],
+            plain: Vec::new(),
+            pos: 0,
+            state: ReadState::Reading,
+        }
+    }
+    /// 返回内部读取器。
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+    fn poll_fill(&mut self, cx: &mut Context<'_>) -> Poll<IoResult<()>> {
+        let mut chunk = ReadBuf::new(&mut self.chunk);
+        ready!(Pin::new(&mut self.inner).poll_read(cx, &mut chunk))?;
+        let filled = chunk.filled();
+        Poll::Ready(if filled.is_empty() {
+            self.state = ReadState::Finished;
+            self.decoder.finish(&mut self.plain)
+        } else {
+            self.decoder.update(filled, &mut self.plain)
+        })
+    }
+}
+impl<R: AsyncRead + Unpin> AsyncRead for AsyncDecryptReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<IoResult<()>> {
+        let this = self.get_mut();
+        while this.pos == this.plain.len() {
+            match this.state {
+                ReadState::Failed => return Poll::Ready(Err(failed_error())),
+                ReadState::Finished => return Poll::Ready(Ok(())),
+                ReadState::Reading if buf.remaining() == 0 => return Poll::Ready(Ok(())),
+                ReadState::Reading => {}
+            }
+            this.plain.clear();
+            this.pos = 0;
+            if let Err(e) = ready!(this.poll_fill(cx)) {
+                this.state = ReadState::Failed;
+                this.plain.clear();
+                return Poll::Ready(Err(e));
+            }
+        }
+        let n = buf.remaining().min(this.plain.len() - this.pos);
+        buf.put_slice(&this.plain[this.pos..this.pos + n]);
+        this.pos += n;
+        Poll::Ready(Ok(()))
+    }
+}
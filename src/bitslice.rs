@@ -0,0 +1,529 @@
+// --- 位切片多块引擎 ---
+//
+// 状态按位平面存放：`st[cell][bit]` 的第 j 位是第 j 个块中该 cell 的该位。
+// 轮函数的每一层都写成位平面上的与/异或/换线，没有依赖数据或密钥的分支和下标：
+//   - S-box 与 GF(2^9) (x^9 + x^4 + 1) 中的求逆仿射等价：S(x) = A·x^-1 + 0xb5，
+//     求逆用 Itoh–Tsujii 链 x^510 = ((x^3)^(2^2)·x^3)^(2^4)·... 只需 3 次乘法
+//   - MDS、BitRotate 是固定的线性换线
+//   - stream_fwd / vtx_shuffle 的平移量来自密钥，用掩码选择实现
+use crate::{BLOCK_SIZE, CELLS, COLS, CipherCtx, DIRS, Layers, ROWS, RoundKey, VEC};
+use std::ops::{BitAnd, BitXor, Not};
+
+// S(x) = A·x^-1 ^ SBOX_C；A 与 A^-1 按列给出 (第 j 列 = A·e_j)
+const SBOX_A: [u16; 9] = [0xd6, 0x1fc, 0x1c7, 0x125, 0xbd, 0x177, 0x109, 0x85, 0x31];
+const SBOX_A_INV: [u16; 9] = [0xa9, 0x3f, 0x1a4, 0x139, 0x17c, 0xd5, 0x16b, 0x18d, 0x1d0];
+const SBOX_C: u16 = 0xb5;
+
+/// 一个位平面字：每一位对应一个块。
+pub(crate) trait Lane:
+    Copy + BitAnd<Output = Self> + BitXor<Output = Self> + Not<Output = Self>
+{
+    const ZERO: Self;
+    /// `bit` 的最低位为 1 时返回全 1，否则全 0 (无分支)。
+    fn mask(bit: u16) -> Self;
+}
+impl Lane for u64 {
+    const ZERO: Self = 0;
+    #[inline(always)]
+    fn mask(bit: u16) -> Self {
+        0u64.wrapping_sub((bit & 1) as u64)
+    }
+}
+/// 由若干个 64 块一组的 u64 位平面拼成的 Lane。
+pub(crate) trait BlockLane: Lane {
+    /// 每个 Lane 含几个 u64，即一批有 `64 * WORDS` 块。
+    const WORDS: usize;
+    fn from_words(words: &[u64]) -> Self;
+    fn to_words(self, words: &mut [u64]);
+}
+impl BlockLane for u64 {
+    const WORDS: usize = 1;
+    #[inline(always)]
+    fn from_words(words: &[u64]) -> Self {
+        words[0]
+    }
+    #[inline(always)]
+    fn to_words(self, words: &mut [u64]) {
+        words[0] = self;
+    }
+}
+// 单块常数时间路径用：每一位对应同一块中的一个 cell
+impl Lane for u32 {
+    const ZERO: Self = 0;
+    #[inline(always)]
+    fn mask(bit: u16) -> Self {
+        0u32.wrapping_sub((bit & 1) as u32)
+    }
+}
+
+type Cell<L> = [L; 9];
+type Planes<L> = [Cell<L>; CELLS];
+
+/// 可移植实现每批并行处理的块数。
+pub(crate) const BATCH_BLOCKS: usize = 64;
+// 最宽的 Lane (AVX2) 含 4 个 u64
+const MAX_WORDS: usize = 4;
+
+// 常数时间相等判断，相等返回 1
+#[inline(always)]
+pub(crate) fn ct_eq(a: usize, b: usize) -> u16 {
+    let x = (a ^ b) as u32;
+    (((x | x.wrapping_neg()) >> 31) ^ 1) as u16
+}
+
+// --- GF(2^9) 位平面运算 ---
+#[inline(always)]
+fn xor<L: Lane>(a: &Cell<L>, b: &Cell<L>) -> Cell<L> {
+    let mut out = *a;
+    for i in 0..9 {
+        out[i] = out[i] ^ b[i];
+    }
+    out
+}
+// 17 位乘积按 x^9 = x^4 + 1 约化
+#[inline(always)]
+fn reduce<L: Lane>(mut t: [L; 17]) -> Cell<L> {
+    for k in (9..17).rev() {
+        t[k - 5] = t[k - 5] ^ t[k];
+        t[k - 9] = t[k - 9] ^ t[k];
+    }
+    let mut out = [L::ZERO; 9];
+    out.copy_from_slice(&t[..9]);
+    out
+}
+#[inline(always)]
+fn gf_mul<L: Lane>(a: &Cell<L>, b: &Cell<L>) -> Cell<L> {
+    let mut t = [L::ZERO; 17];
+    for i in 0..9 {
+        for (tj, &bj) in t[i..i + 9].iter_mut().zip(b) {
+            *tj = *tj ^ (a[i] & bj);
+        }
+    }
+    reduce(t)
+}
+#[inline(always)]
+fn gf_square<L: Lane>(a: &Cell<L>) -> Cell<L> {
+    let mut t = [L::ZERO; 17];
+    for i in 0..9 {
+        t[2 * i] = a[i];
+    }
+    reduce(t)
+}
+// 乘以 x
+#[inline(always)]
+fn xtime<L: Lane>(a: &Cell<L>) -> Cell<L> {
+    let mut out = [L::ZERO; 9];
+    out[0] = a[8];
+    out[1..].copy_from_slice(&a[..8]);
+    out[4] = out[4] ^ a[8];
+    out
+}
+// 乘以公开常数 k
+#[inline(always)]
+fn gf_mul_const<L: Lane>(a: &Cell<L>, k: u16) -> Cell<L> {
+    let mut acc = [L::ZERO; 9];
+    let mut x = *a;
+    for i in 0..9 {
+        if (k >> i) & 1 != 0 {
+            acc = xor(&acc, &x);
+        }
+        x = xtime(&x);
+    }
+    acc
+}
+// x^-1 = x^510，0 映射到 0
+#[inline(always)]
+fn gf_inv<L: Lane>(x: &Cell<L>) -> Cell<L> {
+    let x3 = gf_mul(&gf_square(x), x);
+    let x15 = gf_mul(&gf_square(&gf_square(&x3)), &x3);
+    let mut t = x15;
+    for _ in 0..4 {
+        t = gf_square(&t);
+    }
+    let x255 = gf_mul(&t, &x15);
+    gf_square(&x255)
+}
+#[inline(always)]
+fn linear<L: Lane>(cols: &[u16; 9], x: &Cell<L>) -> Cell<L> {
+    let mut out = [L::ZERO; 9];
+    for (&col, &xj) in cols.iter().zip(x) {
+        for (i, o) in out.iter_mut().enumerate() {
+            *o = *o ^ (xj & L::mask(col >> i));
+        }
+    }
+    out
+}
+#[inline(always)]
+fn add_const<L: Lane>(x: &mut Cell<L>, c: u16) {
+    for (i, v) in x.iter_mut().enumerate() {
+        if (c >> i) & 1 != 0 {
+            *v = !*v;
+        }
+    }
+}
+#[inline(always)]
+pub(crate) fn sbox<L: Lane>(x: &Cell<L>) -> Cell<L> {
+    let mut y = linear(&SBOX_A, &gf_inv(x));
+    add_const(&mut y, SBOX_C);
+    y
+}
+#[inline(always)]
+pub(crate) fn inv_sbox<L: Lane>(x: &Cell<L>) -> Cell<L> {
+    let mut y = *x;
+    add_const(&mut y, SBOX_C);
+    gf_inv(&linear(&SBOX_A_INV, &y))
+}
+
+// --- 轮函数各层 ---
+#[inline(always)]
+fn add_round_key<L: Lane>(st: &mut Planes<L>, rk: &RoundKey) {
+    for (cell, &m) in st.iter_mut().zip(&rk.mask_cells) {
+        for (b, p) in cell.iter_mut().enumerate() {
+            *p = *p ^ L::mask(m >> b);
+        }
+    }
+}
+// 列 MDS：circ(1, 2, 4, 8)
+#[inline(always)]
+fn mds<L: Lane>(st: &mut Planes<L>) {
+    for c in 0..COLS {
+        let mut pw = [[[L::ZERO; 9]; 4]; ROWS];
+        for k in 0..ROWS {
+            pw[k][0] = st[k * COLS + c];
+            for e in 1..4 {
+                pw[k][e] = xtime(&pw[k][e - 1]);
+            }
+        }
+        for r in 0..ROWS {
+            let mut acc = [L::ZERO; 9];
+            for k in 0..ROWS {
+                acc = xor(&acc, &pw[k][(k + ROWS - r) % ROWS]);
+            }
+            st[r * COLS + c] = acc;
+        }
+    }
+}
+// 逆 MDS：circ(0x119, 0x23, 0, 0)
+#[inline(always)]
+fn inv_mds<L: Lane>(st: &mut Planes<L>) {
+    for c in 0..COLS {
+        let mut col = [[L::ZERO; 9]; ROWS];
+        for r in 0..ROWS {
+            col[r] = st[r * COLS + c];
+        }
+        for r in 0..ROWS {
+            st[r * COLS + c] = xor(
+                &gf_mul_const(&col[r], crate::INV_MDS_C1),
+                &gf_mul_const(&col[(r + 1) % ROWS], crate::INV_MDS_C2),
+            );
+        }
+    }
+}
+// 网格平移：out[(r + dr, c + dc)] = grid[(r, c)]
+#[inline(always)]
+pub(crate) fn translate<T: Copy>(grid: &[T; CELLS], dr: usize, dc: usize) -> [T; CELLS] {
+    let mut out = *grid;
+    for (idx, o) in out.iter_mut().enumerate() {
+        let r = (idx / COLS + ROWS - dr % ROWS) % ROWS;
+        let c = (idx % COLS + COLS - dc % COLS) % COLS;
+        *o = grid[r * COLS + c];
+    }
+    out
+}
+// 按掩码在三个候选中选择 (恰有一个掩码为全 1)
+#[inline(always)]
+fn select3<L: Lane>(cands: [&[L; CELLS]; 3], masks: &[L; 3]) -> [L; CELLS] {
+    let mut out = [L::ZERO; CELLS];
+    for (i, o) in out.iter_mut().enumerate() {
+        *o = (cands[0][i] & masks[0]) ^ (cands[1][i] & masks[1]) ^ (cands[2][i] & masks[2]);
+    }
+    out
+}
+// 第 d 位沿方向 VEC[perm[d]] 流动；sign = -1 时反向 (解密)
+#[inline(always)]
+fn stream<L: Lane>(st: &mut Planes<L>, perm: &[usize; 9], sign: i8) {
+    for d in 0..DIRS {
+        // 行、列位移分别为 -1 / 0 / +1 的掩码
+        let mut row_sel = [L::ZERO; 3];
+        let mut col_sel = [L::ZERO; 3];
+        for (k, &(dr, dc)) in VEC.iter().enumerate() {
+            let m = L::mask(ct_eq(k, perm[d]));
+            let ri = (dr * sign + 1) as usize;
+            let ci = (dc * sign + 1) as usize;
+            row_sel[ri] = row_sel[ri] ^ m;
+            col_sel[ci] = col_sel[ci] ^ m;
+        }
+        let mut grid = [L::ZERO; CELLS];
+        for i in 0..CELLS {
+            grid[i] = st[i][d];
+        }
+        let g = select3(
+            [
+                &translate(&grid, ROWS - 1, 0),
+                &grid,
+                &translate(&grid, 1, 0),
+            ],
+            &row_sel,
+        );
+        let g = select3(
+            [&translate(&g, 0, COLS - 1), &g, &translate(&g, 0, 1)],
+            &col_sel,
+        );
+        for i in 0..CELLS {
+            st[i][d] = g[i];
+        }
+    }
+}
+// 整体平移 (s, s)，按 s 的三个比特做桶形移位；inverse 时反向
+#[inline(always)]
+fn shuffle<L: Lane>(st: &mut Planes<L>, shift: u16, inverse: bool) {
+    for i in 0..3 {
+        let step = 1usize << i;
+        let moved = if inverse {
+            translate(st, ROWS * COLS - step, ROWS * COLS - step)
+        } else {
+            translate(st, step, step)
+        };
+        let m = L::mask(shift >> i);
+        for c in 0..CELLS {
+            for b in 0..9 {
+                st[c][b] = st[c][b] ^ ((st[c][b] ^ moved[c][b]) & m);
+            }
+        }
+    }
+}
+
+#[inline(always)]
+fn encrypt_round<L: Lane>(st: &mut Planes<L>, rk: &RoundKey, layers: Layers) {
+    add_round_key(st, rk);
+    for cell in st.iter_mut() {
+        *cell = sbox(cell);
+    }
+    if layers.mds {
+        mds(st);
+    }
+    // 9 位循环左移：第 b 位来自第 b - 1 位
+    if layers.bit_rotate {
+        for cell in st.iter_mut() {
+            let top = cell[8];
+            for b in (1..9).rev() {
+                cell[b] = cell[b - 1];
+            }
+            cell[0] = top;
+        }
+    }
+    if layers.stream_fwd {
+        stream(st, &rk.perm, 1);
+    }
+    if layers.vtx_shuffle {
+        shuffle(st, rk.shift, false);
+    }
+}
+#[inline(always)]
+fn decrypt_round<L: Lane>(st: &mut Planes<L>, rk: &RoundKey, layers: Layers) {
+    if layers.vtx_shuffle {
+        shuffle(st, rk.shift, true);
+    }
+    if layers.stream_fwd {
+        stream(st, &rk.perm, -1);
+    }
+    if layers.bit_rotate {
+        for cell in st.iter_mut() {
+            let bottom = cell[0];
+            for b in 0..8 {
+                cell[b] = cell[b + 1];
+            }
+            cell[8] = bottom;
+        }
+    }
+    if layers.mds {
+        inv_mds(st);
+    }
+    for cell in st.iter_mut() {
+        *cell = inv_sbox(cell);
+    }
+    add_round_key(st, rk);
+}
+
+// --- 块与位平面之间的转置 ---
+// 64x64 位矩阵转置：a[i] 的第 j 位 <-> a[j] 的第 i 位
+fn transpose64(a: &mut [u64; 64]) {
+    let mut j = 32;
+    let mut m = 0x0000_0000_FFFF_FFFFu64;
+    while j != 0 {
+        let mut k = 0;
+        while k < 64 {
+            let t = ((a[k] >> j) ^ a[k + j]) & m;
+            a[k + j] ^= t;
+            a[k] ^= t << j;
+            k = (k + j + 1) & !j;
+        }
+        j >>= 1;
+        m ^= m << j;
+    }
+}
+// 第 p 个位平面 = 288 位块的第 p 位 (与 pack_state 的小端位序一致)
+fn load(blocks: &[[u8; BLOCK_SIZE]]) -> Planes<u64> {
+    let mut st = [[0u64; 9]; CELLS];
+    for k in 0..BLOCK_SIZE.div_ceil(8) {
+        let bytes = (BLOCK_SIZE - k * 8).min(8);
+        let mut rows = [0u64; 64];
+        for (row, block) in rows.iter_mut().zip(blocks) {
+            let mut w = [0u8; 8];
+            w[..bytes].copy_from_slice(&block[k * 8..k * 8 + bytes]);
+            *row = u64::from_le_bytes(w);
+        }
+        transpose64(&mut rows);
+        for (i, &plane) in rows[..bytes * 8].iter().enumerate() {
+            let p = k * 64 + i;
+            st[p / 9][p % 9] = plane;
+        }
+    }
+    st
+}
+fn store(st: &Planes<u64>, blocks: &mut [[u8; BLOCK_SIZE]]) {
+    for k in 0..BLOCK_SIZE.div_ceil(8) {
+        let bytes = (BLOCK_SIZE - k * 8).min(8);
+        let mut rows = [0u64; 64];
+        for (i, row) in rows[..bytes * 8].iter_mut().enumerate() {
+            let p = k * 64 + i;
+            *row = st[p / 9][p % 9];
+        }
+        transpose64(&mut rows);
+        for (row, block) in rows.iter().zip(blocks.iter_mut()) {
+            block[k * 8..k * 8 + bytes].copy_from_slice(&row.to_le_bytes()[..bytes]);
+        }
+    }
+}
+
+// 一批最多 MAX_WORDS 组，每组 64 块各自转置，再按位平面拼成 Lane
+#[inline(always)]
+fn load_lanes<L: BlockLane>(batch: &[[u8; BLOCK_SIZE]]) -> Planes<L> {
+    let mut groups = [[[0u64; 9]; CELLS]; MAX_WORDS];
+    for (g, chunk) in groups.iter_mut().zip(batch.chunks(BATCH_BLOCKS)) {
+        *g = load(chunk);
+    }
+    let mut st = [[L::ZERO; 9]; CELLS];
+    for c in 0..CELLS {
+        for b in 0..9 {
+            let mut words = [0u64; MAX_WORDS];
+            for g in 0..L::WORDS {
+                words[g] = groups[g][c][b];
+            }
+            st[c][b] = L::from_words(&words[..L::WORDS]);
+        }
+    }
+    st
+}
+#[inline(always)]
+fn store_lanes<L: BlockLane>(st: &Planes<L>, batch: &mut [[u8; BLOCK_SIZE]]) {
+    let mut groups = [[[0u64; 9]; CELLS]; MAX_WORDS];
+    for c in 0..CELLS {
+        for b in 0..9 {
+            let mut words = [0u64; MAX_WORDS];
+            st[c][b].to_words(&mut words[..L::WORDS]);
+            for g in 0..L::WORDS {
+                groups[g][c][b] = words[g];
+            }
+        }
+    }
+    for (g, chunk) in groups.iter().zip(batch.chunks_mut(BATCH_BLOCKS)) {
+        store(g, chunk);
+    }
+}
+#[inline(always)]
+pub(crate) fn encrypt_blocks_lanes<L: BlockLane>(ctx: &CipherCtx, blocks: &mut [[u8; BLOCK_SIZE]]) {
+    for batch in blocks.chunks_mut(BATCH_BLOCKS * L::WORDS) {
+        let mut st = load_lanes::<L>(batch);
+        for rk in ctx.active_rounds() {
+            encrypt_round(&mut st, rk, ctx.layers());
+        }
+        store_lanes(&st, batch);
+    }
+}
+#[inline(always)]
+pub(crate) fn decrypt_blocks_lanes<L: BlockLane>(ctx: &CipherCtx, blocks: &mut [[u8; BLOCK_SIZE]]) {
+    for batch in blocks.chunks_mut(BATCH_BLOCKS * L::WORDS) {
+        let mut st = load_lanes::<L>(batch);
+        for rk in ctx.active_rounds().iter().rev() {
+            decrypt_round(&mut st, rk, ctx.layers());
+        }
+        store_lanes(&st, batch);
+    }
+}
+
+/// 位切片引擎的实现方式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchBackend {
+    /// 可移植实现，每批 64 块。
+    Portable,
+    /// x86-64 SSE2，每批 128 块。
+    Sse2,
+    /// x86-64 AVX2，每批 256 块。
+    Avx2,
+    /// AArch64 NEON，每批 128 块。
+    Neon,
+}
+impl BatchBackend {
+    /// 当前 CPU 上最快的可用实现；未启用 `simd` 特性时总是 `Portable`。
+    pub fn detect() -> Self {
+        [BatchBackend::Avx2, BatchBackend::Sse2, BatchBackend::Neon]
+            .into_iter()
+            .find(|b| b.is_available())
+            .unwrap_or(BatchBackend::Portable)
+    }
+    /// 本次编译与当前 CPU 是否支持该实现。
+    pub fn is_available(self) -> bool {
+        match self {
+            BatchBackend::Portable => true,
+            #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+            BatchBackend::Sse2 => true,
+            #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+            BatchBackend::Avx2 => std::is_x86_feature_detected!("avx2"),
+            #[cfg(all(feature = "simd", target_arch = "aarch64"))]
+            BatchBackend::Neon => std::arch::is_aarch64_feature_detected!("neon"),
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
+    }
+    /// 用该实现批量加密，结果与 `encrypt_blocks_ctx` 相同。实现不可用时 panic。
+    pub fn encrypt_blocks(self, ctx: &CipherCtx, blocks: &mut [[u8; BLOCK_SIZE]]) {
+        assert!(self.is_available(), "{:?} backend is not available", self);
+        match self {
+            #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+            BatchBackend::Sse2 => crate::simd::encrypt_blocks_sse2(ctx, blocks),
+            // SAFETY: 上面已确认 CPU 支持 AVX2
+            #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+            BatchBackend::Avx2 => unsafe { crate::simd::encrypt_blocks_avx2(ctx, blocks) },
+            #[cfg(all(feature = "simd", target_arch = "aarch64"))]
+            BatchBackend::Neon => crate::simd::encrypt_blocks_neon(ctx, blocks),
+            _ => encrypt_blocks_lanes::<u64>(ctx, blocks),
+        }
+    }
+    /// 用该实现批量解密，结果与 `decrypt_blocks_ctx` 相同。实现不可用时 panic。
+    pub fn decrypt_blocks(self, ctx: &CipherCtx, blocks: &mut [[u8; BLOCK_SIZE]]) {
+        assert!(self.is_available(), "{:?} backend is not available", self);
+        match self {
+            #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+            BatchBackend::Sse2 => crate::simd::decrypt_blocks_sse2(ctx, blocks),
+            // SAFETY: 上面已确认 CPU 支持 AVX2
+            #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+            BatchBackend::Avx2 => unsafe { crate::simd::decrypt_blocks_avx2(ctx, blocks) },
+            #[cfg(all(feature = "simd", target_arch = "aarch64"))]
+            BatchBackend::Neon => crate::simd::decrypt_blocks_neon(ctx, blocks),
+            _ => decrypt_blocks_lanes::<u64>(ctx, blocks),
+        }
+    }
+}
+
+/// 批量加密 36 字节块，结果与逐块调用 `encrypt_block_288` 相同。
+///
+/// 位切片运算，不含依赖数据或密钥的分支和查表；启用 `simd` 特性时
+/// 按 `BatchBackend::detect()` 选用 SIMD 实现。
+pub fn encrypt_blocks_ctx(ctx: &CipherCtx, blocks: &mut [[u8; BLOCK_SIZE]]) {
+    BatchBackend::detect().encrypt_blocks(ctx, blocks)
+}
+/// `encrypt_blocks_ctx` 的逆运算，结果与逐块调用 `decrypt_block_288` 相同。
+pub fn decrypt_blocks_ctx(ctx: &CipherCtx, blocks: &mut [[u8; BLOCK_SIZE]]) {
+    BatchBackend::detect().decrypt_blocks(ctx, blocks)
+}
@@ -0,0 +1,157 @@
+// --- 常数时间单块轮函数 (`constant_time` 特性) ---
+//
+// 与查表版逐位等价，但不含依赖数据或密钥的分支和下标：
+// S-box 在同一块的 32 个 cell 上位切片求值，MDS 用无分支的 GF(2^9) 乘法，
+// stream_fwd / vtx_shuffle 对所有候选平移做掩码选择。
+use crate::bitslice::{ct_eq, inv_sbox, sbox, translate};
+use crate::{
+    CELLS, COLS, INV_MDS_C1, INV_MDS_C2, Layers, MASK_9, MDS_ROW, ROWS, RoundKey, RoundStep, VEC,
+};
+
+#[inline(always)]
+fn mask(bit: u16) -> u16 {
+    0u16.wrapping_sub(bit & 1)
+}
+// 无分支 GF(2^9) 乘法，模 x^9 + x^4 + 1
+#[inline]
+fn gf_mul_ct(mut a: u16, mut b: u16) -> u16 {
+    let mut res = 0;
+    for _ in 0..9 {
+        res ^= a & mask(b);
+        let carry = mask(a >> 8);
+        a = ((a << 1) & MASK_9) ^ (0x11 & carry);
+        b >>= 1;
+    }
+    res
+}
+// 把 32 个 cell 转成 9 个位平面 (每位一个 cell)，求值后再转回
+#[inline]
+fn subcells_with(cells: &mut [u16; CELLS], f: impl Fn(&[u32; 9]) -> [u32; 9]) {
+    let mut planes = [0u32; 9];
+    for (lane, &v) in cells.iter().enumerate() {
+        for (b, p) in planes.iter_mut().enumerate() {
+            *p |= (((v >> b) & 1) as u32) << lane;
+        }
+    }
+    let out = f(&planes);
+    for (lane, v) in cells.iter_mut().enumerate() {
+        *v = 0;
+        for (b, o) in out.iter().enumerate() {
+            *v |= (((o >> lane) & 1) as u16) << b;
+        }
+    }
+}
+#[inline]
+fn apply_mds_ct(cells: &[u16; CELLS]) -> [u16; CELLS] {
+    let mut out = [0u16; CELLS];
+    for c in 0..COLS {
+        for r in 0..ROWS {
+            let mut acc = 0;
+            for k in 0..ROWS {
+                acc ^= gf_mul_ct(cells[k * COLS + c], MDS_ROW[(k + ROWS - r) % ROWS]);
+            }
+            out[r * COLS + c] = acc;
+        }
+    }
+    out
+}
+#[inline]
+fn apply_inv_mds_ct(cells: &[u16; CELLS]) -> [u16; CELLS] {
+    let mut out = [0u16; CELLS];
+    for c in 0..COLS {
+        for r in 0..ROWS {
+            out[r * COLS + c] = gf_mul_ct(cells[r * COLS + c], INV_MDS_C1)
+                ^ gf_mul_ct(cells[((r + 1) % ROWS) * COLS + c], INV_MDS_C2);
+        }
+    }
+    out
+}
+// 第 d 位沿 VEC[perm[d]] 流动 (sign = -1 为逆向)；9 个方向全部计算，按掩码保留一个
+#[inline]
+fn stream_ct(cells: &[u16; CELLS], perm: &[usize; 9], sign: i8) -> [u16; CELLS] {
+    let mut nxt = [0u16; CELLS];
+    for (d, &p) in perm.iter().enumerate() {
+        for (k, &(dr, dc)) in VEC.iter().enumerate() {
+            let m = mask(ct_eq(p, k)) & (1 << d);
+            let moved = translate(
+                cells,
+                (dr * sign).rem_euclid(ROWS as i8) as usize,
+                (dc * sign).rem_euclid(COLS as i8) as usize,
+            );
+            for i in 0..CELLS {
+                nxt[i] |= moved[i] & m;
+            }
+        }
+    }
+    nxt
+}
+// 整体平移 (s, s)，按 s 的三个比特做桶形移位
+#[inline]
+fn shuffle_ct(cells: &mut [u16; CELLS], shift: u16, inverse: bool) {
+    for i in 0..3 {
+        let step = 1usize << i;
+        let moved = if inverse {
+            translate(cells, CELLS - step, CELLS - step)
+        } else {
+            translate(cells, step, step)
+        };
+        let m = mask(shift >> i);
+        for c in 0..CELLS {
+            cells[c] ^= (cells[c] ^ moved[c]) & m;
+        }
+    }
+}
+
+#[inline(always)]
+pub(crate) fn encrypt_round_observed(
+    cells: &mut [u16; CELLS],
+    rk: &RoundKey,
+    layers: Layers,
+    mut observe: impl FnMut(RoundStep, &[u16; CELLS]),
+) {
+    for (c, k) in cells.iter_mut().zip(&rk.mask_cells) {
+        *c ^= k;
+    }
+    observe(RoundStep::SubKeyXor, cells);
+    subcells_with(cells, sbox);
+    // 层开关是公开的结构参数，按它分支不泄露数据或密钥
+    if layers.mds {
+        *cells = apply_mds_ct(cells);
+    }
+    observe(RoundStep::SubCellsMds, cells);
+    if layers.bit_rotate {
+        for c in cells.iter_mut() {
+            *c = ((*c << 1) | (*c >> 8)) & MASK_9;
+        }
+    }
+    observe(RoundStep::BitRotate, cells);
+    if layers.stream_fwd {
+        *cells = stream_ct(cells, &rk.perm, 1);
+    }
+    observe(RoundStep::StreamFwd, cells);
+    if layers.vtx_shuffle {
+        shuffle_ct(cells, rk.shift, false);
+    }
+    observe(RoundStep::VtxShuffle, cells);
+}
+#[inline]
+pub(crate) fn decrypt_round_ctx(cells: &mut [u16; CELLS], rk: &RoundKey, layers: Layers) {
+    if layers.vtx_shuffle {
+        shuffle_ct(cells, rk.shift, true);
+    }
+    if layers.stream_fwd {
+        *cells = stream_ct(cells, &rk.perm, -1);
+    }
+    if layers.bit_rotate {
+        for c in cells.iter_mut() {
+            *c = ((*c >> 1) | (*c << 8)) & MASK_9;
+        }
+    }
+    if layers.mds {
+        *cells = apply_inv_mds_ct(cells);
+    }
+    subcells_with(cells, inv_sbox);
+    for (c, k) in cells.iter_mut().zip(&rk.mask_cells) {
+        *c ^= k;
+    }
+}
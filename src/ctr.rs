@@ -0,0 +1,111 @@
+// --- CTR 模式：可随机定位的密钥流 ---
+use crate::bitslice::BATCH_BLOCKS;
+use crate::{BLOCK_SIZE, CipherCtx, encrypt_block_288, encrypt_blocks_ctx, xor_in_place};
+use std::io::{Error, ErrorKind, Result as IoResult};
+
+pub const CTR_NONCE_SIZE: usize = 28;
+// 剩余整块数达到该值时改用位切片引擎批量生成密钥流
+const BULK_MIN_BLOCKS: usize = BATCH_BLOCKS / 4;
+
+// 计数器块 = 初始块 + index，按 288 位小端整数相加
+fn counter_block(initial: &[u8; BLOCK_SIZE], index: u64) -> [u8; BLOCK_SIZE] {
+    let mut out = *initial;
+    let mut carry = index as u128;
+    for b in out.iter_mut() {
+        if carry == 0 {
+            break;
+        }
+        let sum = *b as u128 + (carry & 0xFF);
+        *b = sum as u8;
+        carry = (carry >> 8) + (sum >> 8);
+    }
+    out
+}
+
+/// 以 `CipherCtx` 为核心的计数器模式流密码。
+///
+/// 第 i 个密钥流块为 `E(i_le64 || nonce)`，加解密是同一操作，不需要填充。
+#[derive(Clone)]
+pub struct Ctr<'a> {
+    ctx: &'a CipherCtx,
+    initial: [u8; BLOCK_SIZE],
+    pos: u64,
+    keystream: [u8; BLOCK_SIZE],
+    cached_block: Option<u64>,
+}
+impl<'a> Ctr<'a> {
+    pub fn new(ctx: &'a CipherCtx, nonce: &[u8; CTR_NONCE_SIZE]) -> Self {
+        ctx.assert_full_strength();
+        let mut initial = [0u8; BLOCK_SIZE];
+        initial[8..].copy_from_slice(nonce);
+        Self::from_initial_block(ctx, initial)
+    }
+    // EAX 直接用 OMAC 输出作为起始计数器块
+    pub(crate) fn from_initial_block(ctx: &'a CipherCtx, initial: [u8; BLOCK_SIZE]) -> Self {
+        Self {
+            ctx,
+            initial,
+            pos: 0,
+            keystream: [0u8; BLOCK_SIZE],
+            cached_block: None,
+        }
+    }
+    /// 把当前位置起的密钥流异或进 `data`，位置随之前进。
+    ///
+    /// # Panics
+    ///
+    /// 结束位置超过 `u64::MAX` 时 panic；需要自行处理时用 [`try_apply_keystream`](Self::try_apply_keystream)。
+    pub fn apply_keystream(&mut self, data: &mut [u8]) {
+        self.try_apply_keystream(data)
+            .expect("CTR keystream position overflowed u64");
+    }
+    /// 同 [`apply_keystream`](Self::apply_keystream)，但结束位置超过 `u64::MAX` 时返回
+    /// `InvalidInput` 错误，`data` 和位置都不变。
+    pub fn try_apply_keystream(&mut self, data: &mut [u8]) -> IoResult<()> {
+        if self.pos.checked_add(data.len() as u64).is_none() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "CTR keystream position would overflow u64.",
+            ));
+        }
+        let mut data = data;
+        while !data.is_empty() {
+            let block = self.pos / BLOCK_SIZE as u64;
+            let offset = (self.pos % BLOCK_SIZE as u64) as usize;
+            let full_blocks = data.len() / BLOCK_SIZE;
+            if offset == 0 && full_blocks >= BULK_MIN_BLOCKS {
+                let n = full_blocks.min(BATCH_BLOCKS);
+                let mut keystream = [[0u8; BLOCK_SIZE]; BATCH_BLOCKS];
+                for (i, ks) in keystream[..n].iter_mut().enumerate() {
+                    *ks = counter_block(&self.initial, block + i as u64);
+                }
+                encrypt_blocks_ctx(self.ctx, &mut keystream[..n]);
+                let (head, rest) = data.split_at_mut(n * BLOCK_SIZE);
+                for (chunk, ks) in head.chunks_exact_mut(BLOCK_SIZE).zip(&keystream) {
+                    xor_in_place(chunk, ks);
+                }
+                self.pos += head.len() as u64;
+                data = rest;
+                continue;
+            }
+            if self.cached_block != Some(block) {
+                self.keystream = encrypt_block_288(self.ctx, &counter_block(&self.initial, block));
+                self.cached_block = Some(block);
+            }
+            let take = (BLOCK_SIZE - offset).min(data.len());
+            let (head, rest) = data.split_at_mut(take);
+            xor_in_place(head, &self.keystream[offset..offset + take]);
+            self.pos += take as u64;
+            data = rest;
+        }
+        Ok(())
+    }
+    /// 跳到密钥流的第 `pos` 个字节。
+    pub fn seek(&mut self, pos: u64) {
+        self.pos = pos;
+    }
+    /// 当前密钥流字节位置。
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+}
@@ -0,0 +1,340 @@
+// --- 自描述的加密文件头 ---
+//
+// magic(4) || version(1) || kdf_id(1) || kdf_params_len(1) || kdf_params
+//          || salt_len(1) || salt || mode_id(1) || nonce_len(1) || nonce
+//
+// 所有整数均为小端序。EAX 与分段模式把整个文件头作为关联数据认证。
+use crate::{PBKDF2_ROUNDS, SALT_SIZE};
+use pbkdf2::pbkdf2_hmac;
+use sha2::Sha256;
+use std::io::{Error, ErrorKind, Read, Result as IoResult};
+
+pub const FILE_MAGIC: [u8; 4] = *b"WVXF";
+pub const FORMAT_VERSION: u8 = 1;
+
+const KDF_PBKDF2_SHA256: u8 = 1;
+const KDF_ARGON2ID: u8 = 2;
+const KDF_SCRYPT: u8 = 3;
+// 文件头可以要求的 KDF 上限。参数在认证之前就要使用，必须同时限制内存和计算量，
+// 否则伪造的文件头能让解密先空转数小时；上限约为默认参数的 100 倍
+/// 允许的最大 KDF 内存 (2 GiB)。
+pub const MAX_KDF_MEMORY: u64 = 2 << 30;
+/// 允许的最大 PBKDF2 迭代次数。
+pub const MAX_PBKDF2_ITERATIONS: u32 = 10_000_000;
+/// 允许的最大 Argon2id 迭代次数。
+pub const MAX_ARGON2_T_COST: u32 = 16;
+/// 允许的最大 Argon2id 并行度 (lane 数)。
+pub const MAX_ARGON2_P_COST: u32 = 64;
+/// 允许的最大 scrypt 并行度 p。
+pub const MAX_SCRYPT_P: u32 = 16;
+const MODE_CBC: u8 = 1;
+const MODE_EAX: u8 = 2;
+const MODE_SEGMENTED: u8 = 3;
+
+fn invalid_data(msg: String) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+/// 口令文件格式使用的分组模式。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StreamMode {
+    /// 无认证的 CBC + PKCS#7，nonce 为32字节 IV。
+    Cbc,
+    /// EAX 认证加密，nonce 为24字节，密文后附32字节标签；解密需先读完整个文件。
+    Eax,
+    /// 分段 EAX (STREAM)，nonce 为19字节前缀，每64 KiB 一个标签；可并行处理，
+    /// 解密时逐段释放已认证的明文。
+    #[default]
+    Segmented,
+}
+impl StreamMode {
+    /// 该模式在文件头中记录的 nonce 长度。
+    pub fn nonce_size(self) -> usize {
+        match self {
+            StreamMode::Cbc => crate::IV_SIZE,
+            StreamMode::Eax => crate::NONCE_SIZE,
+            StreamMode::Segmented => crate::SEGMENT_PREFIX_SIZE,
+        }
+    }
+    fn id(self) -> u8 {
+        match self {
+            StreamMode::Cbc => MODE_CBC,
+            StreamMode::Eax => MODE_EAX,
+            StreamMode::Segmented => MODE_SEGMENTED,
+        }
+    }
+    fn from_id(id: u8) -> IoResult<Self> {
+        match id {
+            MODE_CBC => Ok(StreamMode::Cbc),
+            MODE_EAX => Ok(StreamMode::Eax),
+            MODE_SEGMENTED => Ok(StreamMode::Segmented),
+            _ => Err(invalid_data(format!("Unknown cipher mode id: {}", id))),
+        }
+    }
+}
+
+/// 口令密钥派生函数及其参数。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kdf {
+    /// PBKDF2-HMAC-SHA256。
+    Pbkdf2Sha256 { iterations: u32 },
+    /// Argon2id v1.3，`m_cost` 以 KiB 计。
+    Argon2id {
+        m_cost: u32,
+        t_cost: u32,
+        p_cost: u32,
+    },
+    /// scrypt，N = 2^log_n。
+    Scrypt { log_n: u8, r: u32, p: u32 },
+}
+impl Default for Kdf {
+    /// 新文件默认使用 Argon2id (19 MiB, 2 次迭代, 单线程)。
+    fn default() -> Self {
+        Kdf::Argon2id {
+            m_cost: 19 * 1024,
+            t_cost: 2,
+            p_cost: 1,
+        }
+    }
+}
+impl Kdf {
+    /// 100,000 次迭代的 PBKDF2，与旧版文件格式相同。
+    pub const PBKDF2_DEFAULT: Kdf = Kdf::Pbkdf2Sha256 {
+        iterations: PBKDF2_ROUNDS,
+    };
+    /// scrypt 推荐参数 (N = 2^17, r = 8, p = 1，约 128 MiB)。
+    pub const SCRYPT_DEFAULT: Kdf = Kdf::Scrypt {
+        log_n: 17,
+        r: 8,
+        p: 1,
+    };
+
+    pub fn derive_key(&self, password: &[u8], salt: &[u8]) -> IoResult<[u8; 32]> {
+        self.validate()?;
+        let mut key = [0u8; 32];
+        match *self {
+            Kdf::Pbkdf2Sha256 { iterations } => {
+                pbkdf2_hmac::<Sha256>(password, salt, iterations, &mut key);
+            }
+            Kdf::Argon2id {
+                m_cost,
+                t_cost,
+                p_cost,
+            } => {
+                let params = argon2::Params::new(m_cost, t_cost, p_cost, Some(key.len()))
+                    .map_err(|e| invalid_data(format!("Invalid Argon2id parameters: {}", e)))?;
+                argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params)
+                    .hash_password_into(password, salt, &mut key)
+                    .map_err(|e| invalid_data(format!("Argon2id failed: {}", e)))?;
+            }
+            Kdf::Scrypt { log_n, r, p } => {
+                let params = scrypt::Params::new(log_n, r, p, key.len())
+                    .map_err(|e| invalid_data(format!("Invalid scrypt parameters: {}", e)))?;
+                scrypt::scrypt(password, salt, &params, &mut key)
+                    .map_err(|e| invalid_data(format!("scrypt failed: {}", e)))?;
+            }
+        }
+        Ok(key)
+    }
+    // 参数来自不可信的文件头：拒绝零迭代以及超过 MAX_* 上限的内存和计算量
+    fn validate(&self) -> IoResult<()> {
+        match *self {
+            Kdf::Pbkdf2Sha256 { iterations } => {
+                if iterations == 0 {
+                    return Err(invalid_data("PBKDF2 iteration count is zero.".into()));
+                }
+                if iterations > MAX_PBKDF2_ITERATIONS {
+                    return Err(invalid_data(format!(
+                        "PBKDF2 iteration count {} exceeds the supported maximum of {}.",
+                        iterations, MAX_PBKDF2_ITERATIONS
+                    )));
+                }
+            }
+            Kdf::Argon2id {
+                m_cost,
+                t_cost,
+                p_cost,
+            } => {
+                if m_cost as u64 * 1024 > MAX_KDF_MEMORY {
+                    return Err(invalid_data(format!(
+                        "Argon2id memory cost {} KiB exceeds the supported maximum.",
+                        m_cost
+                    )));
+                }
+                if t_cost > MAX_ARGON2_T_COST || p_cost > MAX_ARGON2_P_COST {
+                    return Err(invalid_data(format!(
+                        "Argon2id time cost {} or parallelism {} exceeds the supported maximum \
+                         ({} / {}).",
+                        t_cost, p_cost, MAX_ARGON2_T_COST, MAX_ARGON2_P_COST
+                    )));
+                }
+            }
+            Kdf::Scrypt { log_n, r, p } => {
+                if log_n >= 64 || (128u128 * r as u128) << log_n > MAX_KDF_MEMORY as u128 {
+                    return Err(invalid_data(format!(
+                        "scrypt memory cost (log_n = {}, r = {}) exceeds the supported maximum.",
+                        log_n, r
+                    )));
+                }
+                if p == 0 {
+                    return Err(invalid_data("scrypt parallelism is zero.".into()));
+                }
+                if p > MAX_SCRYPT_P {
+                    return Err(invalid_data(format!(
+                        "scrypt parallelism {} exceeds the supported maximum of {}.",
+                        p, MAX_SCRYPT_P
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+    fn id(&self) -> u8 {
+        match self {
+            Kdf::Pbkdf2Sha256 { .. } => KDF_PBKDF2_SHA256,
+            Kdf::Argon2id { .. } => KDF_ARGON2ID,
+            Kdf::Scrypt { .. } => KDF_SCRYPT,
+        }
+    }
+    fn encode_params(&self) -> Vec<u8> {
+        match *self {
+            Kdf::Pbkdf2Sha256 { iterations } => iterations.to_le_bytes().to_vec(),
+            Kdf::Argon2id {
+                m_cost,
+                t_cost,
+                p_cost,
+            } => [m_cost, t_cost, p_cost]
+                .iter()
+                .flat_map(|v| v.to_le_bytes())
+                .collect(),
+            Kdf::Scrypt { log_n, r, p } => {
+                let mut out = vec![log_n];
+                out.extend_from_slice(&r.to_le_bytes());
+                out.extend_from_slice(&p.to_le_bytes());
+                out
+            }
+        }
+    }
+    fn decode(id: u8, params: &[u8]) -> IoResult<Self> {
+        let malformed = || invalid_data(format!("Malformed parameters for KDF id {}.", id));
+        let u32_at = |i: usize| -> IoResult<u32> {
+            let bytes = params.get(i..i + 4).ok_or_else(malformed)?;
+            Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+        };
+        let kdf = match (id, params.len()) {
+            (KDF_PBKDF2_SHA256, 4) => Kdf::Pbkdf2Sha256 {
+                iterations: u32_at(0)?,
+            },
+            (KDF_ARGON2ID, 12) => Kdf::Argon2id {
+                m_cost: u32_at(0)?,
+                t_cost: u32_at(4)?,
+                p_cost: u32_at(8)?,
+            },
+            (KDF_SCRYPT, 9) => Kdf::Scrypt {
+                log_n: params[0],
+                r: u32_at(1)?,
+                p: u32_at(5)?,
+            },
+            (KDF_PBKDF2_SHA256 | KDF_ARGON2ID | KDF_SCRYPT, _) => return Err(malformed()),
+            _ => return Err(invalid_data(format!("Unknown KDF id: {}", id))),
+        };
+        kdf.validate()?;
+        Ok(kdf)
+    }
+}
+
+/// 版本 1 的文件头。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileHeader {
+    pub kdf: Kdf,
+    pub salt: Vec<u8>,
+    pub mode: StreamMode,
+    pub nonce: Vec<u8>,
+}
+impl FileHeader {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let params = self.kdf.encode_params();
+        let mut out = Vec::with_capacity(16 + params.len() + self.salt.len() + self.nonce.len());
+        out.extend_from_slice(&FILE_MAGIC);
+        out.push(FORMAT_VERSION);
+        out.push(self.kdf.id());
+        out.push(params.len() as u8);
+        out.extend_from_slice(&params);
+        out.push(self.salt.len() as u8);
+        out.extend_from_slice(&self.salt);
+        out.push(self.mode.id());
+        out.push(self.nonce.len() as u8);
+        out.extend_from_slice(&self.nonce);
+        out
+    }
+    /// 读取并校验文件头 (包括 magic)。
+    pub fn read_from(reader: &mut impl Read) -> IoResult<Self> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != FILE_MAGIC {
+            return Err(invalid_data("Missing Wave-Vortex file magic.".into()));
+        }
+        Self::read_after_magic(reader)
+    }
+    fn read_after_magic(reader: &mut impl Read) -> IoResult<Self> {
+        let version = read_u8(reader)?;
+        if version != FORMAT_VERSION {
+            return Err(invalid_data(format!(
+                "Unsupported file format version {} (this build reads version {}).",
+                version, FORMAT_VERSION
+            )));
+        }
+        let kdf_id = read_u8(reader)?;
+        let params = read_prefixed(reader)?;
+        let kdf = Kdf::decode(kdf_id, &params)?;
+        let salt = read_prefixed(reader)?;
+        if salt.is_empty() {
+            return Err(invalid_data("Salt is empty.".into()));
+        }
+        let mode = StreamMode::from_id(read_u8(reader)?)?;
+        let nonce = read_prefixed(reader)?;
+        if nonce.len() != mode.nonce_size() {
+            return Err(invalid_data(format!(
+                "Nonce length {} does not match mode {:?}.",
+                nonce.len(),
+                mode
+            )));
+        }
+        Ok(Self {
+            kdf,
+            salt,
+            mode,
+            nonce,
+        })
+    }
+}
+
+fn read_u8(reader: &mut impl Read) -> IoResult<u8> {
+    let mut b = [0u8; 1];
+    reader.read_exact(&mut b)?;
+    Ok(b[0])
+}
+fn read_prefixed(reader: &mut impl Read) -> IoResult<Vec<u8>> {
+    let len = read_u8(reader)? as usize;
+    let mut buf = vec![0u8; len];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+// 文件开头：新格式的文件头，或旧版无头格式 `salt(16) || iv(32) || CBC 密文`
+pub(crate) enum Preamble {
+    Header(FileHeader),
+    Legacy([u8; SALT_SIZE]),
+}
+// 旧文件的随机盐恰好以 magic 开头的概率为 2^-32，此时按新格式解析
+pub(crate) fn read_preamble(reader: &mut impl Read) -> IoResult<Preamble> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if magic == FILE_MAGIC {
+        return Ok(Preamble::Header(FileHeader::read_after_magic(reader)?));
+    }
+    let mut salt = [0u8; SALT_SIZE];
+    salt[..4].copy_from_slice(&magic);
+    reader.read_exact(&mut salt[4..])?;
+    Ok(Preamble::Legacy(salt))
+}
@@ -0,0 +1,135 @@
+// --- 基于 WVX 的实验性哈希 (Miyaguchi–Preneel + MD 强化) ---
+//
+// 链值 H 为 288 位，消息按 36 字节分块：
+//   H_i = E_{H_{i-1}[..32]}(M_i) ^ M_i ^ H_{i-1}
+// 每块都要做一次完整密钥编排，吞吐约为单块加密的一半，仅供研究。
+// 填充为 0x80 || 0* || 比特长度 (u64 大端)，末尾再对全零块做一次压缩，
+// 此前在链值的密钥部分异或结束标志，使输出不是可继续吸收的中间状态；
+// 输出取前 32 字节。带密钥模式的 IV 为 E_K(0)，无密钥模式即 K = 0。
+use crate::{BLOCK_SIZE, CipherCtx, encrypt_block_288, xor_in_place};
+use cipher::consts::U32;
+use digest::{FixedOutput, FixedOutputReset, HashMarker, Output, OutputSizeUser, Reset, Update};
+use once_cell::sync::Lazy;
+
+/// 哈希输出的字节数。
+pub const HASH_SIZE: usize = 32;
+// 长度字段占最后一块末尾的字节数
+const LENGTH_SIZE: usize = 8;
+// 结束压缩前异或到链值首字节的标志
+const FINAL_FLAG: u8 = 0x01;
+
+static UNKEYED_IV: Lazy<[u8; BLOCK_SIZE]> = Lazy::new(|| keyed_iv(&[0u8; 32]));
+
+fn keyed_iv(key: &[u8; 32]) -> [u8; BLOCK_SIZE] {
+    encrypt_block_288(&CipherCtx::new(key), &[0u8; BLOCK_SIZE])
+}
+fn compress(h: &mut [u8; BLOCK_SIZE], block: &[u8; BLOCK_SIZE]) {
+    let ctx = CipherCtx::new(h[..32].try_into().unwrap());
+    let mut out = encrypt_block_288(&ctx, block);
+    xor_in_place(&mut out, block);
+    xor_in_place(&mut out, h);
+    *h = out;
+}
+
+/// 流式哈希，可多次 `update` 后 `finalize`。
+///
+/// 也实现了 RustCrypto 的 `digest::Digest` 所需的 trait。
+#[derive(Clone)]
+pub struct Hasher {
+    iv: [u8; BLOCK_SIZE],
+    h: [u8; BLOCK_SIZE],
+    buf: [u8; BLOCK_SIZE],
+    buf_len: usize,
+    len: u64,
+}
+impl Hasher {
+    pub fn new() -> Self {
+        Self::with_iv(*UNKEYED_IV)
+    }
+    /// 带密钥的哈希，可作为 PRF 使用。
+    pub fn new_keyed(key: &[u8; 32]) -> Self {
+        Self::with_iv(keyed_iv(key))
+    }
+    fn with_iv(iv: [u8; BLOCK_SIZE]) -> Self {
+        Self {
+            iv,
+            h: iv,
+            buf: [0u8; BLOCK_SIZE],
+            buf_len: 0,
+            len: 0,
+        }
+    }
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.len = self.len.wrapping_add(data.len() as u64);
+        while !data.is_empty() {
+            let take = (BLOCK_SIZE - self.buf_len).min(data.len());
+            self.buf[self.buf_len..self.buf_len + take].copy_from_slice(&data[..take]);
+            self.buf_len += take;
+            data = &data[take..];
+            if self.buf_len == BLOCK_SIZE {
+                compress(&mut self.h, &self.buf);
+                self.buf_len = 0;
+            }
+        }
+    }
+    pub fn finalize(mut self) -> [u8; HASH_SIZE] {
+        let bits = self.len.wrapping_mul(8);
+        self.buf[self.buf_len] = 0x80;
+        self.buf[self.buf_len + 1..].fill(0);
+        // 放不下长度字段时多补一整块
+        if self.buf_len + 1 > BLOCK_SIZE - LENGTH_SIZE {
+            compress(&mut self.h, &self.buf);
+            self.buf = [0u8; BLOCK_SIZE];
+        }
+        self.buf[BLOCK_SIZE - LENGTH_SIZE..].copy_from_slice(&bits.to_be_bytes());
+        compress(&mut self.h, &self.buf);
+        self.h[0] ^= FINAL_FLAG;
+        compress(&mut self.h, &[0u8; BLOCK_SIZE]);
+        self.h[..HASH_SIZE].try_into().unwrap()
+    }
+}
+impl Default for Hasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 一次性计算哈希。
+pub fn hash(data: &[u8]) -> [u8; HASH_SIZE] {
+    let mut hasher = Hasher::new();
+    hasher.update(data);
+    hasher.finalize()
+}
+/// 一次性计算带密钥的哈希。
+pub fn keyed_hash(key: &[u8; 32], data: &[u8]) -> [u8; HASH_SIZE] {
+    let mut hasher = Hasher::new_keyed(key);
+    hasher.update(data);
+    hasher.finalize()
+}
+
+// --- RustCrypto `digest` 接口 ---
+impl OutputSizeUser for Hasher {
+    type OutputSize = U32;
+}
+impl Update for Hasher {
+    fn update(&mut self, data: &[u8]) {
+        Hasher::update(self, data);
+    }
+}
+impl FixedOutput for Hasher {
+    fn finalize_into(self, out: &mut Output<Self>) {
+        out.copy_from_slice(&Hasher::finalize(self));
+    }
+}
+impl Reset for Hasher {
+    fn reset(&mut self) {
+        *self = Self::with_iv(self.iv);
+    }
+}
+impl FixedOutputReset for Hasher {
+    fn finalize_into_reset(&mut self, out: &mut Output<Self>) {
+        let fresh = Self::with_iv(self.iv);
+        out.copy_from_slice(&std::mem::replace(self, fresh).finalize());
+    }
+}
+impl HashMarker for Hasher {}
@@ -1,21 +1,80 @@
+use cipher::consts::{U32, U36};
+use cipher::{AlgorithmName, BlockCipher, Key, KeyInit, KeySizeUser};
+#[cfg(not(feature = "constant_time"))]
 use once_cell::sync::Lazy;
 use pbkdf2::pbkdf2_hmac;
-use rand::{RngCore, thread_rng};
+use rand::{CryptoRng, RngCore, thread_rng};
 use sha2::Sha256;
 use std::io::{Cursor, Read, Result as IoResult, Write};
 use wasm_bindgen::prelude::*;
+
+mod adapters;
+mod aead;
+#[cfg(feature = "async")]
+mod async_io;
+mod bitslice;
+#[cfg(feature = "constant_time")]
+mod ct;
+mod ctr;
+mod format;
+mod hash;
+mod linear;
+mod mac;
+mod sector;
+mod segmented;
+#[cfg(feature = "simd")]
+mod simd;
+mod siv;
+mod trace;
+mod trail;
+mod tweak;
+
+pub use adapters::{DecryptReader, EncryptWriter};
+pub use aead::{
+    AuthError, NONCE_SIZE, TAG_SIZE, open, open_in_place_detached, open_stream_with_ctx, seal,
+    seal_in_place_detached, seal_stream_with_ctx, seal_stream_with_nonce,
+};
+#[cfg(feature = "async")]
+pub use async_io::{AsyncDecryptReader, AsyncEncryptWriter};
+pub use bitslice::{BatchBackend, decrypt_blocks_ctx, encrypt_blocks_ctx};
+pub use ctr::{CTR_NONCE_SIZE, Ctr};
+pub use format::{
+    FILE_MAGIC, FORMAT_VERSION, FileHeader, Kdf, MAX_ARGON2_P_COST, MAX_ARGON2_T_COST,
+    MAX_KDF_MEMORY, MAX_PBKDF2_ITERATIONS, MAX_SCRYPT_P, StreamMode,
+};
+pub use hash::{HASH_SIZE, Hasher, hash, keyed_hash};
+pub use linear::{LinearRound, LinearSearch, LinearTrail, SboxMask, search_linear_trail};
+pub use mac::{MAC_SIZE, Mac, MacAlgorithm};
+pub use sector::{decrypt_sector, encrypt_sector};
+pub use segmented::{
+    SEGMENT_PREFIX_SIZE, SEGMENT_SIZE, SegmentedReader, SegmentedWriter, open_segmented_with_ctx,
+    seal_segmented_with_ctx, seal_segmented_with_prefix,
+};
+pub use siv::{
+    SIV_SIZE, WRAPPED_KEY_SIZE, open_deterministic, seal_deterministic, unwrap_key, wrap_key,
+};
+pub use trace::{RoundStep, RoundTrace, format_grid, trace_encrypt};
+pub use trail::{
+    TrailModel, differential_model, linear_model, mds_branch_number, mds_linear_branch_number,
+};
+pub use tweak::{TWEAK_SIZE, TweakableCtx, decrypt_block_tweak, encrypt_block_tweak};
+
+use format::Preamble;
 // --- 常量定义 ---
 const MASK_9: u16 = (1 << 9) - 1; // 9-bit mask: 0x1FF
 const ROWS: usize = 4;
 const COLS: usize = 8;
 const CELLS: usize = ROWS * COLS;
 const DIRS: usize = 9;
-const ROUNDS: usize = 24;
+/// 完整密码的轮数。
+pub const ROUNDS: usize = 24;
+const BLOCK_SIZE: usize = CELLS * 9 / 8; // 288 位状态 = 36 字节
 // --- 文件加密相关常量 ---
 const SALT_SIZE: usize = 16;
 const PLAINTEXT_BLOCK_SIZE: usize = 32;
 const CIPHERTEXT_BLOCK_SIZE: usize = 36;
-const IV_SIZE: usize = PLAINTEXT_BLOCK_SIZE; // IV大小必须等于明文块大小
+/// CBC 初始向量的字节数 (等于明文块大小)。
+pub const IV_SIZE: usize = PLAINTEXT_BLOCK_SIZE;
 const PBKDF2_ROUNDS: u32 = 100_000; // 密钥派生迭代次数
 
 // 预计算的S-box
@@ -58,38 +117,40 @@ pub const SBOX: [u16; 512] = [
 #[rustfmt::skip]
 pub const INV_SBOX: [u16; 512] = [
     0x49, 0x1cd, 0x54, 0x90, 0xb6, 0xcf, 0x13b, 0x114, 0x21, 0xb0, 0x1fd, 0x96, 0x1bc, 0x9f, 0xc0, 0x155,
- 0x1a2, 0xf2, 0x1ac, 0x6d, 0x109, 0x1e6, 0x165, 0xf9, 0x14e, 0xb, 0x82, 0x50, 0x3b, 0x88, 0x14e, 0x1f6,
- 0x8e, 0x122, 0x12b, 0x1c8, 0x174, 0x45, 0x59, 0x124, 0xe4, 0x1e4, 0x99, 0x181, 0xeb, 0xec, 0x3f, 0x46,
- 0x1d, 0x7f, 0x1c9, 0x1ea, 0xce, 0x45, 0x188, 0xe1, 0x1aa, 0x138, 0x13e, 0x1ed, 0x14c, 0x1cc, 0x184, 0xa8,
- 0x8, 0x1d2, 0x48, 0x132, 0xd6, 0x44, 0x3f, 0xe1, 0x1b6, 0xeb, 0x1f4, 0x12f, 0x174, 0xd, 0x4c, 0x129,
- 0x186, 0x19d, 0x185, 0x6b, 0x1d3, 0xf7, 0x15a, 0x7c, 0x166, 0x9e, 0x1a0, 0xbb, 0x117, 0xaf, 0xf1, 0x1a5,
+ 0x1a2, 0xf2, 0x1ac, 0x6d, 0x109, 0x1e6, 0x165, 0xf9, 0x14e, 0xb, 0x82, 0x50, 0x3b, 0x88, 0x33, 0x1f6,
+ 0x8e, 0x122, 0x12b, 0x1c8, 0x172, 0x1e7, 0x59, 0x124, 0xe4, 0x45, 0x99, 0x74, 0x123, 0x16a, 0x85, 0xc6,
+ 0x46, 0x7f, 0x1c9, 0x1ea, 0xce, 0x1ba, 0x188, 0xe1, 0x1aa, 0x138, 0x13e, 0x1ed, 0x14c, 0x1cc, 0x184, 0xa8,
+ 0x8, 0x1d2, 0x132, 0xd6, 0x158, 0x5c, 0x3f, 0x1df, 0x1b6, 0xeb, 0x1f4, 0x12f, 0x174, 0x64, 0x68, 0x129,
+ 0x186, 0x19d, 0x185, 0x6b, 0x1d3, 0xf7, 0x15a, 0x7c, 0x166, 0xdc, 0x1a0, 0xbb, 0x117, 0xaf, 0x92, 0x1a5,
  0xef, 0x18, 0x8b, 0x1, 0xd, 0x4a, 0x4b, 0x135, 0xb1, 0x60, 0x1a, 0x110, 0x1a7, 0x78, 0x149, 0x31,
- 0x11f, 0x171, 0x145, 0x1b5, 0x7a, 0x168, 0xd0, 0x1c2, 0x1d1, 0x1da, 0xf6, 0xc2, 0x140, 0x170, 0x35, 0x1a4,
- 0x193, 0x133, 0xea, 0xec, 0x1c8, 0x85, 0x1c6, 0x93, 0x197, 0xe1, 0x55, 0xdd, 0x105, 0xbf, 0xf8, 0x10a,
- 0x19, 0x1a2, 0xf1, 0x94, 0xc, 0x163, 0x96, 0x9b, 0x1b2, 0x16f, 0x9a, 0x9e, 0x1d4, 0x9e, 0x4c, 0x9a,
- 0xab, 0x12a, 0x8b, 0xdb, 0x1f5, 0xbd, 0x16, 0xde, 0x1c1, 0x134, 0x13c, 0x143, 0x1ca, 0x6c, 0x199, 0x15,
- 0x19b, 0x1e0, 0x7d, 0xb6, 0x37, 0x49, 0x86, 0x1d9, 0x1c0, 0x1e, 0x1c5, 0x1f9, 0x115, 0x1ef, 0xb8, 0x89,
- 0x194, 0x7, 0x3, 0x173, 0x4, 0x44, 0x116, 0xa0, 0xed, 0xc7, 0xa7, 0x107, 0x113, 0x1e2, 0x5d, 0x1a6,
- 0xe, 0xf3, 0x40, 0x125, 0x1a3, 0xba, 0x16e, 0xb5, 0x145, 0x161, 0x17d, 0xfe, 0x8f, 0xee, 0x119, 0x1af,
- 0x5b, 0x69, 0x1e7, 0x1f3, 0x1cb, 0x11e, 0x1e8, 0x1e5, 0x168, 0x36, 0x151, 0x81, 0x1c7, 0xdf, 0x57, 0xee,
- 0x173, 0x172, 0x67, 0x1e9, 0xd9, 0xbe, 0xc6, 0x160, 0x5, 0x5e, 0x154, 0xbf, 0xbc, 0x137, 0x27, 0xc3,
- 0x167, 0x1fa, 0x1f7, 0xfb, 0xe2, 0x144, 0xd7, 0x19f, 0xce, 0xda, 0x63, 0x130, 0x76, 0x94, 0x11a, 0xfc,
- 0x101, 0x43, 0x18d, 0x152, 0x1a2, 0x61, 0x9d, 0x41, 0xfb, 0x7a, 0x112, 0x3d, 0x8e, 0xcc, 0x18e, 0x127,
- 0xf5, 0x15e, 0x1ca, 0x1be, 0x11b, 0xe8, 0x4d, 0x17, 0x190, 0x1b4, 0xd8, 0x157, 0x1ae, 0x184, 0x1fc, 0xc4,
- 0x14d, 0xa2, 0x48, 0x13f, 0x102, 0x16d, 0x126, 0x4e, 0x1b3, 0x18b, 0x1ab, 0x4c, 0x180, 0x195, 0x1bb, 0x192,
- 0x15b, 0xb2, 0x1a9, 0x1a1, 0x8a, 0x11d, 0x12d, 0xfd, 0x71, 0x108, 0xcd, 0x99, 0x65, 0x1c4, 0x178, 0xd3,
- 0x87, 0x1e9, 0xaa, 0xa3, 0xad, 0x19d, 0x178, 0x14b, 0xf9, 0x1ec, 0x81, 0x13d, 0xc1, 0x169, 0x145, 0x59,
+ 0x11f, 0x171, 0x145, 0x1b5, 0x1fe, 0x168, 0xd0, 0x1c2, 0x1d1, 0x1da, 0xf6, 0xc2, 0x140, 0x170, 0x35, 0x1a4,
+ 0x193, 0x133, 0xea, 0xec, 0x23, 0x1ad, 0x1c6, 0x93, 0x197, 0x1e1, 0x55, 0xdd, 0x105, 0x1bf, 0xf8, 0x10a,
+ 0x19, 0x142, 0xf1, 0x16c, 0xc, 0x163, 0x12c, 0x9b, 0x1b2, 0x16f, 0x179, 0x10e, 0x1d4, 0x17e, 0x4c, 0x9a,
+ 0xab, 0x12a, 0x62, 0xdb, 0x1f5, 0x1bd, 0x16, 0x1de, 0x1c1, 0x134, 0x13c, 0x143, 0x32, 0x6c, 0x199, 0x15,
+ 0x19b, 0x1e0, 0x7d, 0x10d, 0x37, 0x0, 0x86, 0x1d9, 0x1c0, 0x1e, 0x1c5, 0x1f9, 0x115, 0x1ef, 0xb8, 0x89,
+ 0x194, 0x7, 0x3, 0xf0, 0x1a8, 0x44, 0x116, 0xa0, 0xed, 0xc7, 0xa7, 0x107, 0x113, 0x1e2, 0x5d, 0x1a6,
+ 0xe, 0x191, 0x40, 0x125, 0x1a3, 0xba, 0x16e, 0xb5, 0x72, 0x161, 0x17d, 0xfe, 0x8f, 0xee, 0x119, 0x1af,
+ 0x5b, 0x69, 0x25, 0x1f3, 0x16b, 0x11e, 0x1e8, 0x1e5, 0x100, 0x36, 0x151, 0x181, 0x1c7, 0x17f, 0x57, 0x1ee,
+ 0x173, 0x24, 0x67, 0x159, 0xd9, 0xbe, 0x2f, 0x160, 0x5, 0x5e, 0x154, 0xbf, 0xbc, 0x137, 0x27, 0xc3,
+ 0x167, 0x1fa, 0x1f7, 0xe3, 0xe2, 0x144, 0xd7, 0x19f, 0x34, 0xda, 0x63, 0x130, 0x76, 0x94, 0x11a, 0xfc,
+ 0x101, 0x43, 0x18d, 0x152, 0x10, 0x61, 0x9d, 0x41, 0xfb, 0x7a, 0x112, 0x3d, 0x20, 0xcc, 0x18e, 0x127,
+ 0xf5, 0x15e, 0x1ca, 0x1be, 0x11b, 0xe8, 0x4d, 0x17, 0x190, 0x1b4, 0xd8, 0x157, 0x1ae, 0x3e, 0x1fc, 0xc4,
+ 0x14d, 0xa2, 0x48, 0x13f, 0x102, 0x16d, 0x126, 0x4e, 0x1b3, 0x18b, 0x1ab, 0x9e, 0x180, 0x195, 0x1bb, 0x192,
+ 0x15b, 0xb2, 0x1a9, 0x1a1, 0x8a, 0x11d, 0x12d, 0xfd, 0x71, 0x108, 0xcd, 0x2a, 0x65, 0x1c4, 0x156, 0xd3,
+ 0x87, 0x1e9, 0xaa, 0xa3, 0xad, 0x51, 0x178, 0x14b, 0x120, 0x1ec, 0x81, 0x13d, 0xc1, 0x169, 0x17a, 0x26,
  0x2b, 0x111, 0x175, 0xd2, 0x6f, 0x19a, 0xae, 0x136, 0x1e4, 0x162, 0xb7, 0x121, 0x7b, 0x66, 0x28, 0x1d,
- 0xc8, 0x19c, 0x23, 0x14a, 0x95, 0x75, 0x38, 0x1f1, 0xf4, 0x15a, 0x141, 0x1cf, 0x13a, 0x13a, 0xfa, 0x1f,
- 0xdf, 0xac, 0x1dc, 0x1fb, 0x11f, 0x1f8, 0x97, 0x98, 0x1b7, 0x44, 0x18a, 0xe7, 0x1db, 0x176, 0xe9, 0x12e,
- 0x42, 0x67, 0xa9, 0xc3, 0x6a, 0x39, 0xa4, 0xf, 0x182, 0x1d0, 0x1c2, 0xa, 0x1b0, 0x12e, 0xd1, 0x1f0,
- 0x104, 0x54, 0x1ac, 0x52, 0x15f, 0x1dd, 0xa6, 0x196, 0xbd, 0x1d3, 0xc9, 0xb3, 0x177, 0x164, 0x16a, 0x1b9,
- 0x10b, 0x6b, 0x15d, 0x10c, 0xde, 0x198, 0x14c, 0x139, 0x35, 0x150, 0x14, 0x166, 0x8c, 0x1d6, 0x29, 0xd4,
- 0x30, 0x8d, 0xfa, 0x106, 0x1f2, 0xe8, 0x1c8, 0x9, 0xcb, 0xca, 0xf2, 0x95, 0x153, 0xf9, 0x98, 0x1db,
- 0xd5, 0x6d, 0x6e, 0x1d5, 0x1a5, 0x15c, 0x13b, 0x1e3, 0x189, 0xa1, 0xf9, 0x1b1, 0x4f, 0x1fb, 0x148, 0xe6,
- 0x18f, 0x1ff, 0x123, 0x14f, 0x188, 0x187, 0x1c, 0x1d9, 0x18c, 0x1bd, 0xb4, 0x1ce, 0x1b8, 0x11c, 0xe0, 0x1b5,
- 0x17b, 0x1eb, 0x1a0, 0x1d7, 0x19e, 0x142, 0x103, 0x4, 0x85, 0xe5, 0x1e, 0x1d8, 0x105, 0x131, 0x50, 0x17c,
+ 0xc8, 0x19c, 0x84, 0x14a, 0x1cb, 0x75, 0x38, 0x1f1, 0xf4, 0x56, 0x141, 0x1cf, 0x3a, 0x13a, 0xfa, 0x1f,
+ 0xdf, 0xac, 0x1dc, 0x1fb, 0x70, 0x1f8, 0x97, 0x80, 0x1b7, 0xc5, 0x18a, 0xe7, 0x47, 0x176, 0xe9, 0x12e,
+ 0x42, 0xf3, 0xa9, 0xff, 0x6a, 0x39, 0xa4, 0xf, 0x182, 0x1d0, 0x77, 0xa, 0x1b0, 0x146, 0xd1, 0x1f0,
+ 0x104, 0x2, 0x12, 0x52, 0x15f, 0x1dd, 0xa6, 0x196, 0xbd, 0x9c, 0xc9, 0xb3, 0x177, 0x164, 0x2d, 0x1b9,
+ 0x10b, 0x53, 0x15d, 0x10c, 0xde, 0x198, 0x3c, 0x139, 0x7e, 0x150, 0x14, 0x58, 0x8c, 0x1d6, 0x29, 0xd4,
+ 0x30, 0x8d, 0x118, 0x106, 0x1f2, 0x83, 0x22, 0x9, 0xcb, 0xca, 0x11, 0x95, 0x153, 0x147, 0x98, 0x1db,
+ 0xd5, 0x13, 0x6e, 0x1d5, 0x5f, 0x15c, 0x6, 0x1e3, 0x189, 0xa1, 0x10f, 0x1b1, 0x4f, 0x183, 0x148, 0xe6,
+ 0x18f, 0x1ff, 0x2c, 0x14f, 0x128, 0x187, 0x1c, 0x79, 0x18c, 0xa5, 0xb4, 0x1ce, 0x1b8, 0x11c, 0xe0, 0x73,
+ 0x17b, 0x1eb, 0x5a, 0x1d7, 0x19e, 0x91, 0x103, 0x4, 0x2e, 0xe5, 0xb9, 0x1d8, 0x1c3, 0x131, 0x1b, 0x17c,
 ];
+// 列 MDS 的第一行，矩阵为 circ(1, 2, 4, 8)
+const MDS_ROW: [u16; ROWS] = [0x1, 0x2, 0x4, 0x8];
 const INV_MDS_C1: u16 = 0x119;
 const INV_MDS_C2: u16 = 0x23;
 // 格点流动向量
@@ -125,6 +186,25 @@ fn gf_mul(mut a: u16, mut b: u16) -> u16 {
     }
     res & MASK_9
 }
+#[inline]
+fn xor_in_place(dst: &mut [u8], src: &[u8]) {
+    for (d, s) in dst.iter_mut().zip(src.iter()) {
+        *d ^= *s;
+    }
+}
+// 读满 buf 或直到 EOF；管道等读取器可能一次只返回部分数据
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> IoResult<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
 fn generate_perm(seed: u64) -> [usize; 9] {
     let mut seq = [0, 1, 2, 3, 4, 5, 6, 7, 8];
     let mut hash = seed;
@@ -135,6 +215,7 @@ fn generate_perm(seed: u64) -> [usize; 9] {
     }
     seq
 }
+#[cfg(not(feature = "constant_time"))]
 fn stream_fwd(cells: &[u16; CELLS], perm: &[usize; 9]) -> [u16; CELLS] {
     let mut nxt = [0u16; CELLS];
     for (idx, &val) in cells.iter().enumerate() {
@@ -151,6 +232,7 @@ fn stream_fwd(cells: &[u16; CELLS], perm: &[usize; 9]) -> [u16; CELLS] {
     }
     nxt
 }
+#[cfg(not(feature = "constant_time"))]
 fn vtx_shuffle(cells: &[u16; CELLS], s: u16) -> [u16; CELLS] {
     let mut out = [0u16; CELLS];
     let s_usize = s as usize;
@@ -163,6 +245,7 @@ fn vtx_shuffle(cells: &[u16; CELLS], s: u16) -> [u16; CELLS] {
     }
     out
 }
+#[cfg(not(feature = "constant_time"))]
 fn inv_vtx_shuffle(cells: &[u16; CELLS], s: u16) -> [u16; CELLS] {
     let mut out = [0u16; CELLS];
     let s_usize = s as usize;
@@ -175,6 +258,7 @@ fn inv_vtx_shuffle(cells: &[u16; CELLS], s: u16) -> [u16; CELLS] {
     }
     out
 }
+#[cfg(not(feature = "constant_time"))]
 fn inv_stream_fwd(cells: &[u16; CELLS], perm: &[usize; 9]) -> [u16; CELLS] {
     let mut nxt = [0u16; CELLS];
     for (idx, &val) in cells.iter().enumerate() {
@@ -201,9 +285,7 @@ fn ascon_round(s: &mut [u64; 5], rc: u64) {
     for i in 0..5 {
         t[i] = s[i] ^ (!s[(i + 1) % 5] & s[(i + 2) % 5]);
     }
-    for i in 0..5 {
-        s[i] = t[i];
-    }
+    s.copy_from_slice(&t);
     s[1] ^= s[0];
     s[0] ^= s[4];
     s[3] ^= s[2];
@@ -216,8 +298,8 @@ fn ascon_round(s: &mut [u64; 5], rc: u64) {
 }
 fn ascon_p(state: &mut [u64; 5], rnds: usize) {
     let start_round = 12 - rnds;
-    for i in start_round..12 {
-        ascon_round(state, RC[i]);
+    for &rc in &RC[start_round..] {
+        ascon_round(state, rc);
     }
 }
 fn key_schedule(master_key: &[u8; 32]) -> Vec<([u8; 36], [usize; 9], u16)> {
@@ -251,9 +333,7 @@ fn pack_state(cells: &[u16; CELLS]) -> [u8; 36] {
         for bit in 0..9 {
             let byte_idx = bit_pos / 8;
             let bit_idx = bit_pos % 8;
-            if (cell >> bit) & 1 != 0 {
-                out[byte_idx] |= 1 << bit_idx;
-            }
+            out[byte_idx] |= (((cell >> bit) & 1) as u8) << bit_idx;
             bit_pos += 1;
         }
     }
@@ -262,7 +342,7 @@ fn pack_state(cells: &[u16; CELLS]) -> [u8; 36] {
 fn unpack_state(ct: &[u8; 36]) -> [u16; CELLS] {
     let mut cells = [0u16; CELLS];
     let mut bit_pos = 0;
-    for cell_idx in 0..CELLS {
+    for cell in cells.iter_mut() {
         let mut val = 0u16;
         for bit in 0..9 {
             let byte_idx = bit_pos / 8;
@@ -270,7 +350,7 @@ fn unpack_state(ct: &[u8; 36]) -> [u16; CELLS] {
             val |= (((ct[byte_idx] >> bit_idx) & 1) as u16) << bit;
             bit_pos += 1;
         }
-        cells[cell_idx] = val;
+        *cell = val;
     }
     cells
 }
@@ -282,12 +362,45 @@ struct RoundKey {
     perm: [usize; 9],
     shift: u16,
 }
+/// 轮函数中可单独关闭的层，供缩减轮和消融实验使用；默认全部开启。
+///
+/// 关闭某层后加密仍是置换，但已不是 WVX，不能用于实际加密。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layers {
+    pub mds: bool,
+    pub bit_rotate: bool,
+    pub stream_fwd: bool,
+    pub vtx_shuffle: bool,
+}
+impl Layers {
+    pub const ALL: Layers = Layers {
+        mds: true,
+        bit_rotate: true,
+        stream_fwd: true,
+        vtx_shuffle: true,
+    };
+}
+impl Default for Layers {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
 #[derive(Clone)]
 pub struct CipherCtx {
     rounds: [RoundKey; ROUNDS],
+    n_rounds: usize,
+    layers: Layers,
 }
 impl CipherCtx {
     pub fn new(master_key: &[u8; 32]) -> Self {
+        Self::with_rounds(master_key, ROUNDS)
+    }
+    /// 只运行前 `n` 轮的缩减轮实例，子密钥与完整密码的前 `n` 轮相同。
+    ///
+    /// `n` 超过 [`ROUNDS`] 时 panic；`n == 0` 得到恒等置换。只能用于分组层面的分析：
+    /// 交给 AEAD、CBC、分段、SIV、MAC 或 CTR 等模式时会 panic。
+    pub fn with_rounds(master_key: &[u8; 32], n: usize) -> Self {
+        assert!(n <= ROUNDS, "round count {n} exceeds {ROUNDS}");
         let subs = key_schedule(master_key);
         let mut rounds: [RoundKey; ROUNDS] = array_init::array_init(|_| RoundKey {
             mask_cells: [0u16; CELLS],
@@ -301,10 +414,63 @@ impl CipherCtx {
                 shift,
             };
         }
-        Self { rounds }
+        Self {
+            rounds,
+            n_rounds: n,
+            layers: Layers::ALL,
+        }
+    }
+    /// 按 `layers` 关闭部分层，加解密和各批量引擎都会遵循；与 [`with_rounds`](Self::with_rounds)
+    /// 一样不能交给加密模式。
+    pub fn with_layers(mut self, layers: Layers) -> Self {
+        self.layers = layers;
+        self
+    }
+    /// 实际运行的轮数。
+    pub fn round_count(&self) -> usize {
+        self.n_rounds
+    }
+    pub fn layers(&self) -> Layers {
+        self.layers
+    }
+    /// 完整轮数且所有层开启，即 [`CipherCtx::new`] 得到的上下文。
+    pub fn is_full_strength(&self) -> bool {
+        self.n_rounds == ROUNDS && self.layers == Layers::ALL
+    }
+    // 加密模式、MAC 等面向实际数据的入口调用；缩减轮和消融实例只能用于分组层面的分析
+    #[track_caller]
+    pub(crate) fn assert_full_strength(&self) {
+        assert!(
+            self.is_full_strength(),
+            "reduced-round or layer-ablated CipherCtx ({} rounds, {:?}) used for encryption",
+            self.n_rounds,
+            self.layers
+        );
+    }
+    pub(crate) fn active_rounds(&self) -> &[RoundKey] {
+        &self.rounds[..self.n_rounds]
+    }
+    /// 实际运行的各轮子密钥 (密钥编排的输出)，供已知答案测试和其他实现核对。
+    pub fn subkeys(&self) -> Vec<Subkey> {
+        self.active_rounds()
+            .iter()
+            .map(|rk| Subkey {
+                mask: pack_state(&rk.mask_cells),
+                perm: rk.perm,
+                shift: rk.shift,
+            })
+            .collect()
     }
 }
+/// 一轮的子密钥：轮密钥掩码 (按状态打包为36字节)、StreamFwd 的方向置换和 VtxShuffle 的位移。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Subkey {
+    pub mask: [u8; 36],
+    pub perm: [usize; 9],
+    pub shift: u16,
+}
 // --- 2. T-Tables for S-box + MDS ---
+#[cfg(not(feature = "constant_time"))]
 static T0: Lazy<[[u16; 4]; 512]> = Lazy::new(|| {
     let mut t = [[0u16; 4]; 512];
     for x in 0..512 {
@@ -318,6 +484,7 @@ static T0: Lazy<[[u16; 4]; 512]> = Lazy::new(|| {
     }
     t
 });
+#[cfg(not(feature = "constant_time"))]
 static T1: Lazy<[[u16; 4]; 512]> = Lazy::new(|| {
     let mut t = [[0u16; 4]; 512];
     for x in 0..512 {
@@ -331,6 +498,7 @@ static T1: Lazy<[[u16; 4]; 512]> = Lazy::new(|| {
     }
     t
 });
+#[cfg(not(feature = "constant_time"))]
 static T2: Lazy<[[u16; 4]; 512]> = Lazy::new(|| {
     let mut t = [[0u16; 4]; 512];
     for x in 0..512 {
@@ -344,6 +512,7 @@ static T2: Lazy<[[u16; 4]; 512]> = Lazy::new(|| {
     }
     t
 });
+#[cfg(not(feature = "constant_time"))]
 static T3: Lazy<[[u16; 4]; 512]> = Lazy::new(|| {
     let mut t = [[0u16; 4]; 512];
     for x in 0..512 {
@@ -357,68 +526,26 @@ static T3: Lazy<[[u16; 4]; 512]> = Lazy::new(|| {
     }
     t
 });
-// --- 2. 查表版 MDS ---
-static MUL_1: Lazy<[u16; 512]> = Lazy::new(|| {
-    let mut t = [0u16; 512];
-    for x in 0..512 {
-        t[x] = x as u16;
-    }
-    t
-});
-static MUL_2: Lazy<[u16; 512]> = Lazy::new(|| {
-    let mut t = [0u16; 512];
-    for x in 0..512 {
-        t[x] = gf_mul(0x02, x as u16);
-    }
-    t
-});
-static MUL_4: Lazy<[u16; 512]> = Lazy::new(|| {
-    let mut t = [0u16; 512];
-    for x in 0..512 {
-        t[x] = gf_mul(0x04, x as u16);
-    }
-    t
-});
-static MUL_8: Lazy<[u16; 512]> = Lazy::new(|| {
-    let mut t = [0u16; 512];
-    for x in 0..512 {
-        t[x] = gf_mul(0x08, x as u16);
-    }
-    t
-});
+// --- 2. 查表版逆 MDS ---
+#[cfg(not(feature = "constant_time"))]
 static MUL_23: Lazy<[u16; 512]> = Lazy::new(|| {
     let mut t = [0u16; 512];
-    for x in 0..512 {
-        t[x] = gf_mul(INV_MDS_C2, x as u16);
+    for (x, v) in t.iter_mut().enumerate() {
+        *v = gf_mul(INV_MDS_C2, x as u16);
     }
     t
 });
+#[cfg(not(feature = "constant_time"))]
 static MUL_119: Lazy<[u16; 512]> = Lazy::new(|| {
     let mut t = [0u16; 512];
-    for x in 0..512 {
-        t[x] = gf_mul(INV_MDS_C1, x as u16);
+    for (x, v) in t.iter_mut().enumerate() {
+        *v = gf_mul(INV_MDS_C1, x as u16);
     }
     t
 });
-#[inline]
-fn apply_mds_lookup(cells: &[u16; CELLS]) -> [u16; CELLS] {
-    let mut out = [0u16; CELLS];
-    for c in 0..COLS {
-        let a = cells[0 * COLS + c] as usize;
-        let b = cells[1 * COLS + c] as usize;
-        let d = cells[2 * COLS + c] as usize;
-        let e = cells[3 * COLS + c] as usize;
-        let r0 = MUL_1[a] ^ MUL_2[b] ^ MUL_4[d] ^ MUL_8[e];
-        let r1 = MUL_8[a] ^ MUL_1[b] ^ MUL_2[d] ^ MUL_4[e];
-        let r2 = MUL_4[a] ^ MUL_8[b] ^ MUL_1[d] ^ MUL_2[e];
-        let r3 = MUL_2[a] ^ MUL_4[b] ^ MUL_8[d] ^ MUL_1[e];
-        out[0 * COLS + c] = r0;
-        out[1 * COLS + c] = r1;
-        out[2 * COLS + c] = r2;
-        out[3 * COLS + c] = r3;
-    }
-    out
-}
+// 下标写成 row * COLS + c 以对齐矩阵的行
+#[allow(clippy::identity_op, clippy::erasing_op)]
+#[cfg(not(feature = "constant_time"))]
 #[inline]
 fn apply_inv_mds_lookup(cells: &[u16; CELLS]) -> [u16; CELLS] {
     let mut out = [0u16; CELLS];
@@ -439,6 +566,8 @@ fn apply_inv_mds_lookup(cells: &[u16; CELLS]) -> [u16; CELLS] {
     out
 }
 
+#[allow(clippy::identity_op, clippy::erasing_op)]
+#[cfg(not(feature = "constant_time"))]
 #[inline]
 fn apply_sub_mds_fused(cells: &[u16; CELLS]) -> [u16; CELLS] {
     let mut out = [0u16; CELLS];
@@ -458,184 +587,170 @@ fn apply_sub_mds_fused(cells: &[u16; CELLS]) -> [u16; CELLS] {
     }
     out
 }
-// --- 3. S‑box 代换：高速查表 + 可选常时位切片 ---
+// --- 3. S‑box 代换 (查表) ---
+#[cfg(not(feature = "constant_time"))]
 #[inline]
 fn subcells_32(cells: &mut [u16; CELLS]) {
-    #[cfg(feature = "constant_time")]
-    {
-        subcells_bitslice_32(cells);
-        return;
-    }
     for v in cells.iter_mut() {
         *v = SBOX[*v as usize];
     }
 }
+#[cfg(not(feature = "constant_time"))]
 #[inline]
 fn inv_subcells_32(cells: &mut [u16; CELLS]) {
-    #[cfg(feature = "constant_time")]
-    {
-        inv_subcells_bitslice_32(cells);
-        return;
-    }
     for v in cells.iter_mut() {
         *v = INV_SBOX[*v as usize];
     }
 }
-// --- 3. 位切片 S-box (常数时间) ---
+// --- 4. 优化后的轮函数 ---
+/// 单块加解密 (`encrypt_block_288` 等) 使用的轮函数实现。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundImpl {
+    /// S-box 与 MDS 融合查表，下标依赖秘密数据。
+    Tables,
+    /// `constant_time` 特性：没有依赖秘密数据或密钥的分支和下标。
+    ConstantTime,
+}
+#[cfg(not(feature = "constant_time"))]
+pub const ROUND_IMPL: RoundImpl = RoundImpl::Tables;
+#[cfg(feature = "constant_time")]
+pub const ROUND_IMPL: RoundImpl = RoundImpl::ConstantTime;
+#[cfg(feature = "constant_time")]
+use ct::{decrypt_round_ctx, encrypt_round_observed};
+
 #[inline]
-fn subcells_bitslice_32(cells: &mut [u16; CELLS]) {
-    let mut in_planes = [0u32; 9];
-    for lane in 0..CELLS {
-        let v = cells[lane];
-        for b in 0..9 {
-            if (v >> b) & 1 != 0 {
-                in_planes[b] |= 1u32 << lane;
-            }
-        }
-    }
-    let mut out_planes = [0u32; 9];
-    for v in 0..512u16 {
-        let mut mask = 0xFFFF_FFFFu32;
-        for b in 0..9 {
-            let bit_is_one = ((v >> b) & 1) != 0;
-            mask &= if bit_is_one {
-                in_planes[b]
-            } else {
-                !in_planes[b]
-            };
-        }
-        if mask == 0 {
-            continue;
-        }
-        let s = SBOX[v as usize];
-        for b in 0..9 {
-            if ((s >> b) & 1) != 0 {
-                out_planes[b] |= mask;
-            }
-        }
+fn encrypt_round_ctx(cells: &mut [u16; CELLS], rk: &RoundKey, layers: Layers) {
+    encrypt_round_observed(cells, rk, layers, |_, _| {});
+}
+// 每一步之后调用 observe，供 trace_encrypt 使用；空闭包会被内联消除
+#[cfg(not(feature = "constant_time"))]
+#[inline(always)]
+fn encrypt_round_observed(
+    cells: &mut [u16; CELLS],
+    rk: &RoundKey,
+    layers: Layers,
+    mut observe: impl FnMut(RoundStep, &[u16; CELLS]),
+) {
+    for (c, k) in cells.iter_mut().zip(&rk.mask_cells) {
+        *c ^= k;
     }
-    for lane in 0..CELLS {
-        let mut v = 0u16;
-        for b in 0..9 {
-            let bit = (out_planes[b] >> lane) & 1;
-            v |= (bit as u16) << b;
-        }
-        cells[lane] = v;
+    observe(RoundStep::SubKeyXor, cells);
+    if layers.mds {
+        *cells = apply_sub_mds_fused(cells);
+    } else {
+        subcells_32(cells);
     }
-}
-#[inline]
-fn inv_subcells_bitslice_32(cells: &mut [u16; CELLS]) {
-    let mut in_planes = [0u32; 9];
-    for lane in 0..CELLS {
-        let v = cells[lane];
-        for b in 0..9 {
-            if (v >> b) & 1 != 0 {
-                in_planes[b] |= 1u32 << lane;
-            }
+    observe(RoundStep::SubCellsMds, cells);
+    if layers.bit_rotate {
+        for c in cells.iter_mut() {
+            *c = ((*c << 1) | (*c >> 8)) & MASK_9;
         }
     }
-    let mut out_planes = [0u32; 9];
-    for v in 0..512u16 {
-        let mut mask = 0xFFFF_FFFFu32;
-        for b in 0..9 {
-            let bit_is_one = ((v >> b) & 1) != 0;
-            mask &= if bit_is_one {
-                in_planes[b]
-            } else {
-                !in_planes[b]
-            };
-        }
-        if mask == 0 {
-            continue;
-        }
-        let s = INV_SBOX[v as usize];
-        for b in 0..9 {
-            if ((s >> b) & 1) != 0 {
-                out_planes[b] |= mask;
-            }
-        }
+    observe(RoundStep::BitRotate, cells);
+    if layers.stream_fwd {
+        *cells = stream_fwd(cells, &rk.perm);
     }
-    for lane in 0..CELLS {
-        let mut v = 0u16;
-        for b in 0..9 {
-            let bit = (out_planes[b] >> lane) & 1;
-            v |= (bit as u16) << b;
-        }
-        cells[lane] = v;
+    observe(RoundStep::StreamFwd, cells);
+    if layers.vtx_shuffle {
+        *cells = vtx_shuffle(cells, rk.shift);
     }
+    observe(RoundStep::VtxShuffle, cells);
 }
-// --- 4. 优化后的轮函数 ---
+#[cfg(not(feature = "constant_time"))]
 #[inline]
-fn encrypt_round_ctx(cells: &mut [u16; CELLS], rk: &RoundKey) {
-    for i in 0..CELLS {
-        cells[i] ^= rk.mask_cells[i];
+fn decrypt_round_ctx(cells: &mut [u16; CELLS], rk: &RoundKey, layers: Layers) {
+    if layers.vtx_shuffle {
+        *cells = inv_vtx_shuffle(cells, rk.shift);
     }
-    *cells = apply_sub_mds_fused(cells);
-    for i in 0..CELLS {
-        cells[i] = ((cells[i] << 1) | (cells[i] >> 8)) & MASK_9;
+    if layers.stream_fwd {
+        *cells = inv_stream_fwd(cells, &rk.perm);
     }
-    *cells = stream_fwd(cells, &rk.perm);
-    *cells = vtx_shuffle(cells, rk.shift);
-}
-#[inline]
-fn decrypt_round_ctx(cells: &mut [u16; CELLS], rk: &RoundKey) {
-    *cells = inv_vtx_shuffle(cells, rk.shift);
-    *cells = inv_stream_fwd(cells, &rk.perm);
-    for i in 0..CELLS {
-        cells[i] = ((cells[i] >> 1) | (cells[i] << 8)) & MASK_9;
+    if layers.bit_rotate {
+        for c in cells.iter_mut() {
+            *c = ((*c >> 1) | (*c << 8)) & MASK_9;
+        }
+    }
+    if layers.mds {
+        *cells = apply_inv_mds_lookup(cells);
     }
-    *cells = apply_inv_mds_lookup(cells);
     inv_subcells_32(cells);
-    for i in 0..CELLS {
-        cells[i] ^= rk.mask_cells[i];
+    for (c, k) in cells.iter_mut().zip(&rk.mask_cells) {
+        *c ^= k;
     }
 }
-// --- 5. 新的基于上下文的块API ---
-pub fn encrypt_block_ctx(ctx: &CipherCtx, pt: &[u8; 32]) -> [u8; 36] {
-    let mut bit_pos = 0;
-    let mut cells = [0u16; CELLS];
-    for cell_idx in 0..CELLS {
-        let mut val = 0u16;
-        for bit in 0..9 {
-            if bit_pos < 256 {
-                let byte_idx = bit_pos / 8;
-                let bit_idx = bit_pos % 8;
-                val |= (((pt[byte_idx] >> bit_idx) & 1) as u16) << bit;
-                bit_pos += 1;
-            }
-        }
-        cells[cell_idx] = val;
+// --- 5. 288 位等长置换 ---
+/// 用完整的 4x8x9 位状态加密一个36字节块，输入输出等长。
+pub fn encrypt_block_288(ctx: &CipherCtx, block: &[u8; 36]) -> [u8; 36] {
+    let mut cells = unpack_state(block);
+    for rk in ctx.active_rounds() {
+        encrypt_round_ctx(&mut cells, rk, ctx.layers);
     }
-    for r in 0..ROUNDS {
-        encrypt_round_ctx(&mut cells, &ctx.rounds[r]);
+    pack_state(&cells)
+}
+/// `encrypt_block_288` 的逆置换。
+pub fn decrypt_block_288(ctx: &CipherCtx, block: &[u8; 36]) -> [u8; 36] {
+    let mut cells = unpack_state(block);
+    for rk in ctx.active_rounds().iter().rev() {
+        decrypt_round_ctx(&mut cells, rk, ctx.layers);
     }
     pack_state(&cells)
 }
+/// 加密一个块，返回每轮结束后的状态；最后一项即密文。
+pub fn encrypt_block_rounds(ctx: &CipherCtx, block: &[u8; 36]) -> Vec<[u8; 36]> {
+    let mut cells = unpack_state(block);
+    ctx.active_rounds()
+        .iter()
+        .map(|rk| {
+            encrypt_round_ctx(&mut cells, rk, ctx.layers);
+            pack_state(&cells)
+        })
+        .collect()
+}
+// --- 6. 32→36 扩展块API (兼容封装) ---
+/// 加密一个32字节明文块，得到36字节密文。
+///
+/// 等价于把明文末尾补4个零字节后调用 `encrypt_block_288`，
+/// 状态中最后32位不承载明文。需要等长密文时请直接使用 `encrypt_block_288`。
+pub fn encrypt_block_ctx(ctx: &CipherCtx, pt: &[u8; 32]) -> [u8; 36] {
+    let mut block = [0u8; 36];
+    block[..32].copy_from_slice(pt);
+    encrypt_block_288(ctx, &block)
+}
+/// 解密 `encrypt_block_ctx` 产生的36字节密文，返回前32字节明文。
+///
+/// 末尾4字节被直接丢弃，不做校验。
 pub fn decrypt_block_ctx(ctx: &CipherCtx, ct: &[u8; 36]) -> [u8; 32] {
-    let mut cells = unpack_state(ct);
-    for r in (0..ROUNDS).rev() {
-        decrypt_round_ctx(&mut cells, &ctx.rounds[r]);
-    }
+    let block = decrypt_block_288(ctx, ct);
     let mut pt = [0u8; 32];
-    let mut bit_pos = 0;
-    for cell_idx in 0..CELLS {
-        for bit in 0..9 {
-            if bit_pos < 256 {
-                let byte_idx = bit_pos / 8;
-                let bit_idx = bit_pos % 8;
-                let bit_val = (cells[cell_idx] >> bit) & 1;
-                pt[byte_idx] |= (bit_val as u8) << bit_idx;
-                bit_pos += 1;
-            } else {
-                break;
-            }
-        }
-        if bit_pos >= 256 {
-            break;
-        }
-    }
+    pt.copy_from_slice(&block[..32]);
     pt
 }
+// --- 7. RustCrypto `cipher` trait 实现 ---
+impl KeySizeUser for CipherCtx {
+    type KeySize = U32;
+}
+impl KeyInit for CipherCtx {
+    fn new(key: &Key<Self>) -> Self {
+        CipherCtx::new(key.as_ref())
+    }
+}
+impl BlockCipher for CipherCtx {}
+impl AlgorithmName for CipherCtx {
+    fn write_alg_name(f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("WaveVortex")
+    }
+}
+cipher::impl_simple_block_encdec!(
+    <> CipherCtx, U36, ctx, block,
+    encrypt: {
+        let out = encrypt_block_288(ctx, block.get_in().as_ref());
+        block.get_out().copy_from_slice(&out);
+    }
+    decrypt: {
+        let out = decrypt_block_288(ctx, block.get_in().as_ref());
+        block.get_out().copy_from_slice(&out);
+    }
+);
 // --- 公共 API ---
 /// 加密一个32字节的明文块。
 pub fn encrypt_block(pt: &[u8; 32], master_key: &[u8; 32]) -> [u8; 36] {
@@ -649,6 +764,7 @@ pub fn decrypt_block(ct: &[u8; 36], master_key: &[u8; 32]) -> [u8; 32] {
 }
 
 // --- 高级文件加密 API ---
+/// 使用 PBKDF2-HMAC-SHA256 (100,000 次迭代) 派生密钥，即旧版无头文件使用的 KDF。
 pub fn derive_key_from_password(password: &[u8], salt: &[u8]) -> [u8; 32] {
     let mut key = [0u8; 32];
     pbkdf2_hmac::<Sha256>(password, salt, PBKDF2_ROUNDS, &mut key);
@@ -662,12 +778,33 @@ pub fn encrypt_stream_with_ctx(
 ) -> IoResult<()> {
     let mut iv = [0u8; IV_SIZE];
     thread_rng().fill_bytes(&mut iv);
-    writer.write_all(&iv)?;
-    let mut prev_cipher_block = iv;
+    encrypt_stream_with_iv(reader, writer, ctx, &iv)
+}
+/// 与 [`encrypt_stream_with_ctx`] 相同，但使用调用者给定的 IV，输出可复现。
+///
+/// IV 必须不可预测，只应在已知答案测试或 IV 另有可靠来源时使用。
+pub fn encrypt_stream_with_iv(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    ctx: &CipherCtx,
+    iv: &[u8; IV_SIZE],
+) -> IoResult<()> {
+    ctx.assert_full_strength();
+    writer.write_all(iv)?;
+    cbc_encrypt_body(reader, writer, ctx, iv)
+}
+
+fn cbc_encrypt_body(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    ctx: &CipherCtx,
+    iv: &[u8; IV_SIZE],
+) -> IoResult<()> {
+    let mut prev_cipher_block = *iv;
     let mut buffer = [0u8; PLAINTEXT_BLOCK_SIZE];
     let mut has_data = false;
     loop {
-        let bytes_read = reader.read(&mut buffer)?;
+        let bytes_read = read_full(reader, &mut buffer)?;
         if bytes_read == 0 && !has_data {
             // 空输入：不加密
             break;
@@ -711,27 +848,159 @@ pub fn encrypt_stream_with_ctx(
     Ok(())
 }
 
+/// 口令加密的可选参数：分组模式与 KDF 会记录在文件头中。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StreamOptions {
+    pub mode: StreamMode,
+    pub kdf: Kdf,
+    /// 分段模式的工作线程数，0 表示使用全部可用核心；不影响输出。
+    pub threads: usize,
+}
+
+/// 以默认参数 (Argon2id + 分段 EAX) 加密，输出带版本号的自描述文件。
 pub fn encrypt_stream(
     reader: &mut impl Read,
     writer: &mut impl Write,
     password: &[u8],
+) -> IoResult<()> {
+    encrypt_stream_with_options(reader, writer, password, &StreamOptions::default())
+}
+
+pub fn encrypt_stream_with_mode(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    password: &[u8],
+    mode: StreamMode,
+) -> IoResult<()> {
+    let options = StreamOptions {
+        mode,
+        ..StreamOptions::default()
+    };
+    encrypt_stream_with_options(reader, writer, password, &options)
+}
+
+pub fn encrypt_stream_with_options(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    password: &[u8],
+    options: &StreamOptions,
+) -> IoResult<()> {
+    encrypt_stream_with_rng(reader, writer, password, options, &mut thread_rng())
+}
+
+/// 与 [`encrypt_stream_with_options`] 相同，盐和 nonce 依次取自 `rng`；
+/// 种子固定的随机数源可以生成可复现的测试向量。
+pub fn encrypt_stream_with_rng(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    password: &[u8],
+    options: &StreamOptions,
+    rng: &mut (impl RngCore + CryptoRng),
 ) -> IoResult<()> {
     let mut salt = [0u8; SALT_SIZE];
-    thread_rng().fill_bytes(&mut salt);
-    writer.write_all(&salt)?;
-    let key = derive_key_from_password(password, &salt);
+    rng.fill_bytes(&mut salt);
+    let mut nonce = vec![0u8; options.mode.nonce_size()];
+    rng.fill_bytes(&mut nonce);
+    let header = FileHeader {
+        kdf: options.kdf,
+        salt: salt.to_vec(),
+        mode: options.mode,
+        nonce,
+    };
+    encrypt_stream_with_header(reader, writer, password, &header, options.threads)
+}
+
+/// 按调用者给定的文件头 (KDF、盐、模式、nonce) 加密，用于发布和核对已知答案测试。
+///
+/// 同一口令下盐和 nonce 绝不能重复使用；`threads` 只影响分段模式的速度。
+pub fn encrypt_stream_with_header(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    password: &[u8],
+    header: &FileHeader,
+    threads: usize,
+) -> IoResult<()> {
+    let mode = header.mode;
+    if header.salt.is_empty() || header.salt.len() > u8::MAX as usize {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "Salt must be between 1 and 255 bytes.",
+        ));
+    }
+    if header.nonce.len() != mode.nonce_size() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "Nonce length does not match the stream mode.",
+        ));
+    }
+    // 先派生密钥：参数无效时不写出任何内容
+    let key = header.kdf.derive_key(password, &header.salt)?;
+    let header_bytes = header.to_bytes();
+    writer.write_all(&header_bytes)?;
     let ctx = CipherCtx::new(&key);
-    encrypt_stream_with_ctx(reader, writer, &ctx)
+    match mode {
+        StreamMode::Cbc => {
+            cbc_encrypt_body(reader, writer, &ctx, header.nonce[..].try_into().unwrap())
+        }
+        StreamMode::Eax => aead::seal_stream_body(
+            reader,
+            writer,
+            &ctx,
+            header.nonce[..].try_into().unwrap(),
+            &header_bytes,
+        ),
+        StreamMode::Segmented => segmented::seal_segmented_body(
+            reader,
+            writer,
+            &ctx,
+            header.nonce[..].try_into().unwrap(),
+            &header_bytes,
+            threads,
+        ),
+    }
 }
 
+/// 解密 `encrypt_stream_with_ctx` 的 CBC 输出。
+///
+/// CBC 无认证，只能靠 PKCS#7 发现结尾，无法可靠检测在块边界上的截断；
+/// 需要完整性时使用分段模式 ([`SegmentedReader`])。
 pub fn decrypt_stream_with_ctx(
     reader: &mut impl Read,
     writer: &mut impl Write,
     ctx: &CipherCtx,
 ) -> IoResult<()> {
+    ctx.assert_full_strength();
     let mut iv = [0u8; IV_SIZE];
     reader.read_exact(&mut iv)?;
-    let mut prev_cipher_block = iv;
+    cbc_decrypt_body(reader, writer, ctx, &iv)
+}
+
+// 校验 PKCS#7 填充，返回去掉填充后的长度
+fn strip_pkcs7(last_block: &[u8; PLAINTEXT_BLOCK_SIZE]) -> IoResult<usize> {
+    let padding_val = last_block[PLAINTEXT_BLOCK_SIZE - 1];
+    if padding_val == 0 || padding_val > PLAINTEXT_BLOCK_SIZE as u8 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "Invalid PKCS#7 padding: value is zero or too large.",
+        ));
+    }
+    let unpadded_len = PLAINTEXT_BLOCK_SIZE - padding_val as usize;
+    if last_block[unpadded_len..].iter().any(|&b| b != padding_val) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "Invalid PKCS#7 padding: bytes do not match padding value.",
+        ));
+    }
+    Ok(unpadded_len)
+}
+
+fn cbc_decrypt_body(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    ctx: &CipherCtx,
+    iv: &[u8; IV_SIZE],
+) -> IoResult<()> {
+    let mut prev_cipher_block = *iv;
     let mut buffer = [0u8; CIPHERTEXT_BLOCK_SIZE];
     let mut temp_decrypted_block = [0u8; PLAINTEXT_BLOCK_SIZE];
     let mut is_first_block = true;
@@ -757,44 +1026,50 @@ pub fn decrypt_stream_with_ctx(
     }
 
     if is_first_block {
-        return Ok(()); 
+        return Ok(());
     }
-    // Process the final block for padding
-    let last_block = temp_decrypted_block;
-    let padding_val = last_block[PLAINTEXT_BLOCK_SIZE - 1];
-    if padding_val == 0 || padding_val > PLAINTEXT_BLOCK_SIZE as u8 {
-        return Err(std::io::Error::new(
-            std::io::ErrorKind::InvalidData,
-            "Invalid PKCS#7 padding: value is zero or too large.",
-        ));
-    }
-    let unpadded_len = PLAINTEXT_BLOCK_SIZE - padding_val as usize;
-
-    for i in unpadded_len..PLAINTEXT_BLOCK_SIZE {
-        if last_block[i] != padding_val {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                "Invalid PKCS#7 padding: bytes do not match padding value.",
-            ));
-        }
-    }
-
-    writer.write_all(&last_block[..unpadded_len])?;
+    let unpadded_len = strip_pkcs7(&temp_decrypted_block)?;
+    writer.write_all(&temp_decrypted_block[..unpadded_len])?;
 
     Ok(())
 }
 
+/// 解密 `encrypt_stream` 的输出；没有文件头的旧版 CBC 文件也能解密。
 pub fn decrypt_stream(
     reader: &mut impl Read,
     writer: &mut impl Write,
     password: &[u8],
 ) -> IoResult<()> {
-    let mut salt = [0u8; SALT_SIZE];
-    reader.read_exact(&mut salt)?;
-    let key = derive_key_from_password(password, &salt);
+    let header = match format::read_preamble(reader)? {
+        Preamble::Header(header) => header,
+        Preamble::Legacy(salt) => {
+            let key = derive_key_from_password(password, &salt);
+            let ctx = CipherCtx::new(&key);
+            return decrypt_stream_with_ctx(reader, writer, &ctx);
+        }
+    };
+    let key = header.kdf.derive_key(password, &header.salt)?;
     let ctx = CipherCtx::new(&key);
-
-    decrypt_stream_with_ctx(reader, writer, &ctx)
+    match header.mode {
+        StreamMode::Cbc => {
+            cbc_decrypt_body(reader, writer, &ctx, header.nonce[..].try_into().unwrap())
+        }
+        StreamMode::Eax => aead::open_stream_body(
+            reader,
+            writer,
+            &ctx,
+            header.nonce[..].try_into().unwrap(),
+            &header.to_bytes(),
+        ),
+        StreamMode::Segmented => segmented::open_segmented_body(
+            reader,
+            writer,
+            &ctx,
+            header.nonce[..].try_into().unwrap(),
+            &header.to_bytes(),
+            0,
+        ),
+    }
 }
 // --- Wasm 绑定部分 ---
 #[wasm_bindgen]
@@ -820,6 +1095,30 @@ pub fn wasm_decrypt_block(ciphertext: &[u8], key: &[u8]) -> Result<Vec<u8>, JsVa
     Ok(plaintext.to_vec())
 }
 #[wasm_bindgen]
+pub fn wasm_encrypt_block_288(block: &[u8], key: &[u8]) -> Result<Vec<u8>, JsValue> {
+    if block.len() != 36 || key.len() != 32 {
+        return Err(JsValue::from_str(
+            "Block must be 36 bytes and key must be 32 bytes.",
+        ));
+    }
+    let block_arr: [u8; 36] = block.try_into().unwrap();
+    let key_arr: [u8; 32] = key.try_into().unwrap();
+    let ctx = CipherCtx::new(&key_arr);
+    Ok(encrypt_block_288(&ctx, &block_arr).to_vec())
+}
+#[wasm_bindgen]
+pub fn wasm_decrypt_block_288(block: &[u8], key: &[u8]) -> Result<Vec<u8>, JsValue> {
+    if block.len() != 36 || key.len() != 32 {
+        return Err(JsValue::from_str(
+            "Block must be 36 bytes and key must be 32 bytes.",
+        ));
+    }
+    let block_arr: [u8; 36] = block.try_into().unwrap();
+    let key_arr: [u8; 32] = key.try_into().unwrap();
+    let ctx = CipherCtx::new(&key_arr);
+    Ok(decrypt_block_288(&ctx, &block_arr).to_vec())
+}
+#[wasm_bindgen]
 pub fn wasm_encrypt_stream(data: &[u8], password: &[u8]) -> Result<Vec<u8>, JsValue> {
     let mut reader = Cursor::new(data);
     let mut writer = Cursor::new(Vec::new());
@@ -828,6 +1127,51 @@ pub fn wasm_encrypt_stream(data: &[u8], password: &[u8]) -> Result<Vec<u8>, JsVa
         Err(e) => Err(JsValue::from_str(&format!("Encryption failed: {}", e))),
     }
 }
+fn wasm_encrypt_with_kdf(data: &[u8], password: &[u8], kdf: Kdf) -> Result<Vec<u8>, JsValue> {
+    let mut reader = Cursor::new(data);
+    let mut writer = Cursor::new(Vec::new());
+    let options = StreamOptions {
+        kdf,
+        ..StreamOptions::default()
+    };
+    match encrypt_stream_with_options(&mut reader, &mut writer, password, &options) {
+        Ok(_) => Ok(writer.into_inner()),
+        Err(e) => Err(JsValue::from_str(&format!("Encryption failed: {}", e))),
+    }
+}
+#[wasm_bindgen]
+pub fn wasm_encrypt_stream_pbkdf2(
+    data: &[u8],
+    password: &[u8],
+    iterations: u32,
+) -> Result<Vec<u8>, JsValue> {
+    wasm_encrypt_with_kdf(data, password, Kdf::Pbkdf2Sha256 { iterations })
+}
+#[wasm_bindgen]
+pub fn wasm_encrypt_stream_argon2id(
+    data: &[u8],
+    password: &[u8],
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+) -> Result<Vec<u8>, JsValue> {
+    let kdf = Kdf::Argon2id {
+        m_cost,
+        t_cost,
+        p_cost,
+    };
+    wasm_encrypt_with_kdf(data, password, kdf)
+}
+#[wasm_bindgen]
+pub fn wasm_encrypt_stream_scrypt(
+    data: &[u8],
+    password: &[u8],
+    log_n: u8,
+    r: u32,
+    p: u32,
+) -> Result<Vec<u8>, JsValue> {
+    wasm_encrypt_with_kdf(data, password, Kdf::Scrypt { log_n, r, p })
+}
 #[wasm_bindgen]
 pub fn wasm_decrypt_stream(encrypted_data: &[u8], password: &[u8]) -> Result<Vec<u8>, JsValue> {
     let mut reader = Cursor::new(encrypted_data);
@@ -0,0 +1,301 @@
+// --- 线性特征搜索 ---
+//
+// 掩码经过 MDS、BitRotate、StreamFwd、VtxShuffle 的传播是确定的，只有 S 盒要选输出掩码：
+// 活跃 S 盒 (u, v) 的相关度为 W(u, v) / 512，W 为 S 盒的 Walsh 谱，轮密钥只影响符号。
+// 按轮深度优先做分支限界 (Matsui 式)，为控制规模：
+// - 第一轮只有 cell 0 一个活跃 S 盒，不考虑其他起点和多个起始活跃 S 盒；
+// - 每个输入掩码只尝试 |W| 最大的若干输出掩码，同等相关度时先试汉明重量低的；
+//   候选数从 1 逐步放宽到上限，窄搜索很快给出一个界，宽搜索在此界下剪枝；
+// - 限制搜索节点数。
+// 找到的特征真实存在，其相关度只是最优特征相关度的下界，不是最大值；
+// `exhaustive_within_limits` 表示在上述限制内已搜完，不代表全局最优。
+use crate::trail::bit_destination;
+use crate::{
+    CELLS, COLS, CipherCtx, INV_MDS_C1, INV_MDS_C2, Layers, ROWS, RoundKey, SBOX, gf_mul,
+    pack_state,
+};
+use once_cell::sync::Lazy;
+use std::cmp::Reverse;
+
+const SBOX_SIZE: usize = 512;
+
+// Walsh 谱 W[u][v] = Σ_x (-1)^(u·x ⊕ v·S(x))，以及每个输入掩码按 |W| 降序排列的输出掩码
+struct Lat {
+    walsh: Vec<i16>,
+    candidates: Vec<Vec<u16>>,
+}
+impl Lat {
+    fn walsh(&self, u: u16, v: u16) -> i16 {
+        self.walsh[u as usize * SBOX_SIZE + v as usize]
+    }
+    // -log2 |W(u, v) / 512|
+    fn weight(&self, u: u16, v: u16) -> f64 {
+        9.0 - (self.walsh(u, v).unsigned_abs() as f64).log2()
+    }
+}
+static LAT: Lazy<Lat> = Lazy::new(|| {
+    let mut walsh = vec![0i16; SBOX_SIZE * SBOX_SIZE];
+    let mut f = [0i32; SBOX_SIZE];
+    for v in 1..SBOX_SIZE {
+        for (x, fx) in f.iter_mut().enumerate() {
+            *fx = 1 - 2 * ((v as u16 & SBOX[x]).count_ones() & 1) as i32;
+        }
+        // 快速 Walsh–Hadamard 变换，得到所有 u 的 W(u, v)
+        let mut h = 1;
+        while h < SBOX_SIZE {
+            for i in (0..SBOX_SIZE).step_by(2 * h) {
+                for j in i..i + h {
+                    let (a, b) = (f[j], f[j + h]);
+                    f[j] = a + b;
+                    f[j + h] = a - b;
+                }
+            }
+            h *= 2;
+        }
+        for (u, &w) in f.iter().enumerate() {
+            walsh[u * SBOX_SIZE + v] = w as i16;
+        }
+    }
+    let candidates = (0..SBOX_SIZE)
+        .map(|u| {
+            let w = |v: u16| walsh[u * SBOX_SIZE + v as usize];
+            let mut vs: Vec<u16> = (1..SBOX_SIZE as u16).filter(|&v| w(v) != 0).collect();
+            vs.sort_by_key(|&v| (Reverse(w(v).abs()), v.count_ones(), v));
+            vs
+        })
+        .collect();
+    Lat { walsh, candidates }
+});
+
+// 乘常数 c 的转置作用在掩码上：结果第 i 位 = m · (c · x^i)
+fn mask_mul_table(c: u16) -> [u16; SBOX_SIZE] {
+    let mut t = [0u16; SBOX_SIZE];
+    for (m, v) in t.iter_mut().enumerate() {
+        for i in 0..9 {
+            *v |= (((m as u16 & gf_mul(c, 1 << i)).count_ones() & 1) as u16) << i;
+        }
+    }
+    t
+}
+static MASK_C1: Lazy<[u16; SBOX_SIZE]> = Lazy::new(|| mask_mul_table(INV_MDS_C1));
+static MASK_C2: Lazy<[u16; SBOX_SIZE]> = Lazy::new(|| mask_mul_table(INV_MDS_C2));
+
+// S 盒输出掩码经本轮线性层后得到下一轮的输入掩码
+fn propagate(out: &[u16; CELLS], rk: &RoundKey, layers: Layers) -> [u16; CELLS] {
+    let mut y = *out;
+    if layers.mds {
+        // 掩码按 (M^-1)^T 传播；M^-1 的第 r 行是 C1·x_r + C2·x_(r+1)
+        for c in 0..COLS {
+            for k in 0..ROWS {
+                let prev = (k + ROWS - 1) % ROWS;
+                y[k * COLS + c] =
+                    MASK_C1[out[k * COLS + c] as usize] ^ MASK_C2[out[prev * COLS + c] as usize];
+            }
+        }
+    }
+    let mut next = [0u16; CELLS];
+    for (i, &m) in y.iter().enumerate() {
+        for k in 0..9 {
+            if m >> k & 1 != 0 {
+                let (j, b) = bit_destination(i, k, rk, layers);
+                next[j] |= 1 << b;
+            }
+        }
+    }
+    next
+}
+
+/// 线性特征搜索的限制。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LinearSearch {
+    /// 每个 S 盒输入掩码最多尝试的输出掩码个数。
+    pub candidates: usize,
+    /// 搜索节点上限，达到后返回当时最好的特征。
+    pub max_nodes: u64,
+}
+impl Default for LinearSearch {
+    fn default() -> Self {
+        Self {
+            candidates: 2,
+            max_nodes: 1_000_000,
+        }
+    }
+}
+
+/// 一个活跃 S 盒的输入、输出掩码和相关度 (不含轮密钥带来的符号)。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SboxMask {
+    pub cell: usize,
+    pub input: u16,
+    pub output: u16,
+    pub correlation: f64,
+}
+
+/// 特征中的一轮：本轮输入掩码 (按明文块的比特排列打包为 36 字节) 和各活跃 S 盒。
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinearRound {
+    pub input: [u8; 36],
+    pub sboxes: Vec<SboxMask>,
+}
+
+/// 搜索得到的线性特征：`rounds[0].input · P ⊕ output · C` 的相关度约为 ±[`correlation`](Self::correlation)。
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinearTrail {
+    pub rounds: Vec<LinearRound>,
+    /// 最后一轮之后，即密文上的掩码。
+    pub output: [u8; 36],
+    /// 访问的搜索节点数。
+    pub nodes: u64,
+    /// 在 [`LinearSearch`] 的限制 (单一起点、候选数、节点上限) 内已搜完。
+    /// 即使为真，相关度也只是最优特征相关度的下界。
+    pub exhaustive_within_limits: bool,
+}
+impl LinearTrail {
+    /// 各活跃 S 盒相关度绝对值之积。
+    pub fn correlation(&self) -> f64 {
+        self.sboxes().map(|s| s.correlation.abs()).product()
+    }
+    pub fn log2_correlation(&self) -> f64 {
+        self.sboxes().map(|s| s.correlation.abs().log2()).sum()
+    }
+    pub fn active_sboxes(&self) -> usize {
+        self.sboxes().count()
+    }
+    fn sboxes(&self) -> impl Iterator<Item = &SboxMask> {
+        self.rounds.iter().flat_map(|r| &r.sboxes)
+    }
+}
+
+// 一条完整特征：总权重，各轮输入掩码和 S 盒输出掩码
+type Path = (f64, Vec<[u16; CELLS]>, Vec<[u16; CELLS]>);
+
+struct Search<'a> {
+    rounds: &'a [RoundKey],
+    layers: Layers,
+    // 本遍每个 S 盒尝试的输出掩码个数
+    width: usize,
+    max_nodes: u64,
+    // 单个活跃 S 盒的最小权重，用于估计剩余部分的下界
+    w_min: f64,
+    // 当前路径上各轮的输入掩码和 S 盒输出掩码
+    inputs: Vec<[u16; CELLS]>,
+    outputs: Vec<[u16; CELLS]>,
+    best: Option<Path>,
+    nodes: u64,
+    aborted: bool,
+}
+impl Search<'_> {
+    fn best_weight(&self) -> f64 {
+        self.best.as_ref().map_or(f64::INFINITY, |b| b.0)
+    }
+    fn enter_round(&mut self, r: usize, input: [u16; CELLS], weight: f64) {
+        let active: Vec<usize> = (0..CELLS).filter(|&i| input[i] != 0).collect();
+        self.inputs.push(input);
+        self.assign(r, &active, 0, [0u16; CELLS], weight);
+        self.inputs.pop();
+    }
+    // 依次为本轮第 idx 个活跃 S 盒选择输出掩码
+    fn assign(
+        &mut self,
+        r: usize,
+        active: &[usize],
+        idx: usize,
+        mut out: [u16; CELLS],
+        weight: f64,
+    ) {
+        if self.nodes >= self.max_nodes {
+            self.aborted = true;
+            return;
+        }
+        self.nodes += 1;
+        let left = active.len() - idx + self.rounds.len() - r - 1;
+        if weight + left as f64 * self.w_min >= self.best_weight() {
+            return;
+        }
+        if idx == active.len() {
+            self.outputs.push(out);
+            if r + 1 == self.rounds.len() {
+                self.best = Some((weight, self.inputs.clone(), self.outputs.clone()));
+            } else {
+                let next = propagate(&out, &self.rounds[r], self.layers);
+                self.enter_round(r + 1, next, weight);
+            }
+            self.outputs.pop();
+            return;
+        }
+        let cell = active[idx];
+        let u = self.inputs[r][cell];
+        for &v in LAT.candidates[u as usize].iter().take(self.width) {
+            out[cell] = v;
+            self.assign(r, active, idx + 1, out, weight + LAT.weight(u, v));
+        }
+    }
+}
+
+/// 在 `ctx` 的轮数、层开关和轮密钥下搜索相关度最高的线性特征。
+///
+/// 节点上限太小、连一条完整特征都走不到时返回 `None`。
+pub fn search_linear_trail(ctx: &CipherCtx, opts: &LinearSearch) -> Option<LinearTrail> {
+    let rounds = ctx.active_rounds();
+    assert!(!rounds.is_empty(), "trail search needs at least one round");
+    let lat = &*LAT;
+    let best_of = |u: u16| lat.walsh(u, lat.candidates[u as usize][0]).abs();
+    let w_min = (1..SBOX_SIZE as u16)
+        .map(|u| lat.weight(u, lat.candidates[u as usize][0]))
+        .fold(f64::INFINITY, f64::min);
+    let mut search = Search {
+        rounds,
+        layers: ctx.layers(),
+        width: 0,
+        max_nodes: opts.max_nodes,
+        w_min,
+        inputs: Vec::with_capacity(rounds.len()),
+        outputs: Vec::with_capacity(rounds.len()),
+        best: None,
+        nodes: 0,
+        aborted: false,
+    };
+    // 先试单轮相关度最高的输入掩码，尽早得到较紧的界
+    let mut starts: Vec<u16> = (1..SBOX_SIZE as u16).collect();
+    starts.sort_by_key(|&u| (Reverse(best_of(u)), u.count_ones(), u));
+    for width in 1..=opts.candidates {
+        search.width = width;
+        search.aborted = false;
+        for &u in &starts {
+            let mut input = [0u16; CELLS];
+            input[0] = u;
+            search.enter_round(0, input, 0.0);
+        }
+        if search.aborted {
+            break;
+        }
+    }
+    let (_, inputs, outputs) = search.best?;
+    let trail_rounds = inputs
+        .iter()
+        .zip(&outputs)
+        .map(|(input, out)| LinearRound {
+            input: pack_state(input),
+            sboxes: (0..CELLS)
+                .filter(|&i| input[i] != 0)
+                .map(|i| SboxMask {
+                    cell: i,
+                    input: input[i],
+                    output: out[i],
+                    correlation: lat.walsh(input[i], out[i]) as f64 / SBOX_SIZE as f64,
+                })
+                .collect(),
+        })
+        .collect();
+    let last = propagate(
+        &outputs[outputs.len() - 1],
+        &rounds[rounds.len() - 1],
+        ctx.layers(),
+    );
+    Some(LinearTrail {
+        rounds: trail_rounds,
+        output: pack_state(&last),
+        nodes: search.nodes,
+        exhaustive_within_limits: !search.aborted,
+    })
+}
@@ -0,0 +1,296 @@
+// --- CMAC (OMAC1) / PMAC1：基于 288 位置换的消息认证 ---
+//
+// CMAC 逐块串行；PMAC 每块用偏移量 Δ_i 掩码后各自加密再异或求和，
+// 块间互不依赖，交给批量 (位切片 / SIMD) 引擎一次处理多块。
+use crate::aead::{AuthError, tags_equal};
+use crate::{BLOCK_SIZE, CipherCtx, encrypt_block_288, encrypt_blocks_ctx, xor_in_place};
+use cipher::consts::{U32, U36};
+use cipher::{Key, KeyInit, KeySizeUser};
+use digest::{FixedOutput, FixedOutputReset, MacMarker, Output, OutputSizeUser, Reset, Update};
+
+// GF(2^288) 约化多项式 x^288 + x^11 + x^10 + x + 1 的低位部分 (小端字节序)
+const R_288: [u8; 2] = [0x03, 0x0C];
+
+/// 在 GF(2^288) 中乘以 x，块按小端序解释为 288 位整数。
+pub(crate) fn dbl(block: &[u8; BLOCK_SIZE]) -> [u8; BLOCK_SIZE] {
+    let mut out = [0u8; BLOCK_SIZE];
+    let mut carry = 0u8;
+    for i in 0..BLOCK_SIZE {
+        out[i] = (block[i] << 1) | carry;
+        carry = block[i] >> 7;
+    }
+    // 无分支约化
+    let mask = 0u8.wrapping_sub(carry);
+    out[0] ^= R_288[0] & mask;
+    out[1] ^= R_288[1] & mask;
+    out
+}
+
+/// 在 GF(2^288) 中乘以 x^-1 (`dbl` 的逆)。
+pub(crate) fn half(block: &[u8; BLOCK_SIZE]) -> [u8; BLOCK_SIZE] {
+    // 最低位为 1 时先加上约化多项式，使其可被 x 整除
+    let mask = 0u8.wrapping_sub(block[0] & 1);
+    let mut v = *block;
+    v[0] ^= R_288[0] & mask;
+    v[1] ^= R_288[1] & mask;
+    let mut out = [0u8; BLOCK_SIZE];
+    for i in 0..BLOCK_SIZE {
+        let next = if i + 1 < BLOCK_SIZE {
+            v[i + 1]
+        } else {
+            mask & 1
+        };
+        out[i] = (v[i] >> 1) | (next << 7);
+    }
+    out
+}
+
+/// 增量式 CMAC，最后一块用 K1 (完整块) 或 K2 (填充块) 掩码。
+/// 不借用 `CipherCtx`，每次调用时传入，便于自持上下文的流式适配器保存状态。
+#[derive(Clone)]
+pub(crate) struct Cmac {
+    k1: [u8; BLOCK_SIZE],
+    k2: [u8; BLOCK_SIZE],
+    state: [u8; BLOCK_SIZE],
+    buf: [u8; BLOCK_SIZE],
+    buf_len: usize,
+}
+impl Cmac {
+    pub(crate) fn new(ctx: &CipherCtx) -> Self {
+        let l = encrypt_block_288(ctx, &[0u8; BLOCK_SIZE]);
+        let k1 = dbl(&l);
+        let k2 = dbl(&k1);
+        Self {
+            k1,
+            k2,
+            state: [0u8; BLOCK_SIZE],
+            buf: [0u8; BLOCK_SIZE],
+            buf_len: 0,
+        }
+    }
+    pub(crate) fn update(&mut self, ctx: &CipherCtx, mut data: &[u8]) {
+        while !data.is_empty() {
+            // 缓冲区满且后面还有数据：它不是最后一块，可以直接压缩
+            if self.buf_len == BLOCK_SIZE {
+                xor_in_place(&mut self.state, &self.buf);
+                self.state = encrypt_block_288(ctx, &self.state);
+                self.buf_len = 0;
+            }
+            let take = (BLOCK_SIZE - self.buf_len).min(data.len());
+            self.buf[self.buf_len..self.buf_len + take].copy_from_slice(&data[..take]);
+            self.buf_len += take;
+            data = &data[take..];
+        }
+    }
+    pub(crate) fn finalize(mut self, ctx: &CipherCtx) -> [u8; BLOCK_SIZE] {
+        if self.buf_len == BLOCK_SIZE {
+            xor_in_place(&mut self.buf, &self.k1);
+        } else {
+            // 10* 填充
+            self.buf[self.buf_len] = 0x80;
+            for b in self.buf[self.buf_len + 1..].iter_mut() {
+                *b = 0;
+            }
+            xor_in_place(&mut self.buf, &self.k2);
+        }
+        xor_in_place(&mut self.state, &self.buf);
+        encrypt_block_288(ctx, &self.state)
+    }
+}
+
+// PMAC 每攒够这么多块调用一次批量引擎
+const PMAC_BATCH: usize = 64;
+
+/// 增量式 PMAC1：第 i 块的偏移 Δ_i = Δ_{i-1} ^ L·x^ntz(i)，
+/// 最后一块不加密，直接并入校验和。
+#[derive(Clone)]
+pub(crate) struct Pmac {
+    // l[i] = L·x^i，块计数为 u64，ntz 不超过 63
+    l: Vec<[u8; BLOCK_SIZE]>,
+    l_inv: [u8; BLOCK_SIZE],
+    offset: [u8; BLOCK_SIZE],
+    sigma: [u8; BLOCK_SIZE],
+    count: u64,
+    buf: [u8; BLOCK_SIZE],
+    buf_len: usize,
+    batch: Vec<[u8; BLOCK_SIZE]>,
+}
+impl Pmac {
+    pub(crate) fn new(ctx: &CipherCtx) -> Self {
+        let l0 = encrypt_block_288(ctx, &[0u8; BLOCK_SIZE]);
+        let mut l = Vec::with_capacity(64);
+        l.push(l0);
+        for i in 1..64 {
+            l.push(dbl(&l[i - 1]));
+        }
+        Self {
+            l,
+            l_inv: half(&l0),
+            offset: [0u8; BLOCK_SIZE],
+            sigma: [0u8; BLOCK_SIZE],
+            count: 0,
+            buf: [0u8; BLOCK_SIZE],
+            buf_len: 0,
+            batch: Vec::with_capacity(PMAC_BATCH),
+        }
+    }
+    pub(crate) fn update(&mut self, ctx: &CipherCtx, mut data: &[u8]) {
+        while !data.is_empty() {
+            if self.buf_len == BLOCK_SIZE {
+                self.count += 1;
+                xor_in_place(
+                    &mut self.offset,
+                    &self.l[self.count.trailing_zeros() as usize],
+                );
+                let mut block = self.buf;
+                xor_in_place(&mut block, &self.offset);
+                self.batch.push(block);
+                if self.batch.len() == PMAC_BATCH {
+                    self.flush(ctx);
+                }
+                self.buf_len = 0;
+            }
+            let take = (BLOCK_SIZE - self.buf_len).min(data.len());
+            self.buf[self.buf_len..self.buf_len + take].copy_from_slice(&data[..take]);
+            self.buf_len += take;
+            data = &data[take..];
+        }
+    }
+    fn flush(&mut self, ctx: &CipherCtx) {
+        encrypt_blocks_ctx(ctx, &mut self.batch);
+        for block in &self.batch {
+            xor_in_place(&mut self.sigma, block);
+        }
+        self.batch.clear();
+    }
+    pub(crate) fn finalize(mut self, ctx: &CipherCtx) -> [u8; BLOCK_SIZE] {
+        self.flush(ctx);
+        if self.buf_len == BLOCK_SIZE {
+            xor_in_place(&mut self.sigma, &self.buf);
+            xor_in_place(&mut self.sigma, &self.l_inv);
+        } else {
+            self.buf[self.buf_len] = 0x80;
+            for b in self.buf[self.buf_len + 1..].iter_mut() {
+                *b = 0;
+            }
+            xor_in_place(&mut self.sigma, &self.buf);
+        }
+        encrypt_block_288(ctx, &self.sigma)
+    }
+}
+
+/// MAC 标签的字节数 (一个完整块)。
+pub const MAC_SIZE: usize = BLOCK_SIZE;
+
+/// MAC 构造。同一密钥不要同时用于两种构造，也不要与加密共用。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MacAlgorithm {
+    /// CMAC (OMAC1)，逐块串行。
+    #[default]
+    Cmac,
+    /// PMAC1，可并行，长消息更快。
+    Pmac,
+}
+
+#[derive(Clone)]
+enum MacState {
+    Cmac(Cmac),
+    Pmac(Box<Pmac>),
+}
+impl MacState {
+    fn new(ctx: &CipherCtx, algorithm: MacAlgorithm) -> Self {
+        match algorithm {
+            MacAlgorithm::Cmac => MacState::Cmac(Cmac::new(ctx)),
+            MacAlgorithm::Pmac => MacState::Pmac(Box::new(Pmac::new(ctx))),
+        }
+    }
+    fn finalize(self, ctx: &CipherCtx) -> [u8; MAC_SIZE] {
+        match self {
+            MacState::Cmac(mac) => mac.finalize(ctx),
+            MacState::Pmac(mac) => mac.finalize(ctx),
+        }
+    }
+}
+
+/// 增量式消息认证码，自持密钥上下文。
+///
+/// 也实现了 RustCrypto 的 `digest::Mac`；通过 `KeyInit` 构造时为 CMAC。
+#[derive(Clone)]
+pub struct Mac {
+    ctx: CipherCtx,
+    state: MacState,
+}
+impl Mac {
+    /// 以 CMAC 构造。
+    pub fn cmac(key: &[u8; 32]) -> Self {
+        Self::with_ctx(CipherCtx::new(key), MacAlgorithm::Cmac)
+    }
+    /// 以 PMAC 构造。
+    pub fn pmac(key: &[u8; 32]) -> Self {
+        Self::with_ctx(CipherCtx::new(key), MacAlgorithm::Pmac)
+    }
+    /// 复用已展开的轮密钥。
+    pub fn with_ctx(ctx: CipherCtx, algorithm: MacAlgorithm) -> Self {
+        ctx.assert_full_strength();
+        let state = MacState::new(&ctx, algorithm);
+        Self { ctx, state }
+    }
+    pub fn algorithm(&self) -> MacAlgorithm {
+        match self.state {
+            MacState::Cmac(_) => MacAlgorithm::Cmac,
+            MacState::Pmac(_) => MacAlgorithm::Pmac,
+        }
+    }
+    pub fn update(&mut self, data: &[u8]) {
+        match &mut self.state {
+            MacState::Cmac(mac) => mac.update(&self.ctx, data),
+            MacState::Pmac(mac) => mac.update(&self.ctx, data),
+        }
+    }
+    pub fn finalize(self) -> [u8; MAC_SIZE] {
+        self.state.finalize(&self.ctx)
+    }
+    /// 常数时间比较标签。
+    pub fn verify(self, tag: &[u8; MAC_SIZE]) -> Result<(), AuthError> {
+        if tags_equal(&self.finalize(), tag) {
+            Ok(())
+        } else {
+            Err(AuthError)
+        }
+    }
+}
+// --- RustCrypto `digest::Mac` 接口 ---
+impl KeySizeUser for Mac {
+    type KeySize = U32;
+}
+impl KeyInit for Mac {
+    fn new(key: &Key<Self>) -> Self {
+        Mac::cmac(key.as_ref())
+    }
+}
+impl OutputSizeUser for Mac {
+    type OutputSize = U36;
+}
+impl Update for Mac {
+    fn update(&mut self, data: &[u8]) {
+        Mac::update(self, data);
+    }
+}
+impl FixedOutput for Mac {
+    fn finalize_into(self, out: &mut Output<Self>) {
+        out.copy_from_slice(&Mac::finalize(self));
+    }
+}
+impl Reset for Mac {
+    fn reset(&mut self) {
+        self.state = MacState::new(&self.ctx, self.algorithm());
+    }
+}
+impl FixedOutputReset for Mac {
+    fn finalize_into_reset(&mut self, out: &mut Output<Self>) {
+        let fresh = MacState::new(&self.ctx, self.algorithm());
+        let state = std::mem::replace(&mut self.state, fresh);
+        out.copy_from_slice(&state.finalize(&self.ctx));
+    }
+}
+impl MacMarker for Mac {}
@@ -1,13 +1,515 @@
 mod analysis;
 
-fn main() {
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use rand::{RngCore, thread_rng};
+use std::error::Error;
+use std::fs::{self, File, OpenOptions};
+use std::hint::black_box;
+use std::io::{self, BufReader, BufWriter, Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Instant;
+use wave_vortex::{
+    CipherCtx, Kdf, ROUNDS, StreamMode, StreamOptions, decrypt_block_288, decrypt_stream,
+    decrypt_stream_with_ctx, encrypt_block_288, encrypt_stream_with_ctx,
+    encrypt_stream_with_options, open_segmented_with_ctx, open_stream_with_ctx,
+    seal_segmented_with_ctx, seal_stream_with_ctx, trace_encrypt,
+};
+
+type CliResult<T> = Result<T, Box<dyn Error>>;
+
+#[derive(Parser)]
+#[command(
+    name = "wave_vortex",
+    version,
+    about = "Wave-Vortex (WVX) 288-bit block cipher toolkit"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Encrypt a file (or stdin) with a password or a raw key file
+    Encrypt(CryptArgs),
+    /// Decrypt a file (or stdin) produced by `encrypt`
+    Decrypt(CryptArgs),
+    /// Generate a random 256-bit raw key
+    Keygen {
+        /// Write the raw 32-byte key to this file (default: print hex to stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Overwrite the output file if it already exists
+        #[arg(short, long)]
+        force: bool,
+    },
+    /// Measure key schedule, block and stream throughput on this machine
+    Bench {
+        /// Amount of data for the stream measurements, in MiB
+        #[arg(long, default_value_t = 8)]
+        size_mib: usize,
+    },
+    /// Run the S-box and avalanche analysis suite
+    Analyze {
+        /// Also write differential and linear trail models (CPLEX LP) for 1..=N rounds into this directory
+        #[arg(long, value_name = "DIR")]
+        trail_models: Option<PathBuf>,
+        /// Also search for high-correlation linear trails for 1..=N rounds and write a JSON report to this file
+        #[arg(long, value_name = "PATH")]
+        linear_report: Option<PathBuf>,
+        /// Highest round count N for the trail models and the linear trail search
+        #[arg(long, default_value_t = 8, value_name = "N")]
+        trail_rounds: usize,
+        /// Raw 256-bit key file for the key-dependent trail models and search; default: the analysis key
+        #[arg(long, value_name = "PATH")]
+        key_file: Option<PathBuf>,
+    },
+    /// Encrypt one block and print the state after every step of every round
+    Trace {
+        /// Raw 256-bit key file (32 bytes, or 64 hex digits); default: all-zero key
+        #[arg(long, value_name = "PATH")]
+        key_file: Option<PathBuf>,
+        /// 36-byte plaintext block as 72 hex digits; default: all zeros
+        #[arg(long, value_name = "HEX")]
+        block: Option<String>,
+    },
+}
+
+#[derive(Args)]
+struct CryptArgs {
+    /// Input file (default: stdin)
+    #[arg(short, long)]
+    input: Option<PathBuf>,
+    /// Output file (default: stdout)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Overwrite the output file if it already exists
+    #[arg(short, long)]
+    force: bool,
+    /// Cipher mode; password files record it in the header, raw-key files need it on decrypt too
+    #[arg(long, value_enum, default_value_t = ModeArg::Segmented)]
+    mode: ModeArg,
+    /// Password KDF for encryption; decryption reads it from the file header
+    #[arg(long, value_enum, default_value_t = KdfArg::Argon2id)]
+    kdf: KdfArg,
+    /// Worker threads for segmented mode (0 = all cores); the output does not depend on it
+    #[arg(long, default_value_t = 0)]
+    threads: usize,
+    #[command(flatten)]
+    key: KeySource,
+}
+
+#[derive(Args)]
+#[group(multiple = false)]
+struct KeySource {
+    /// Read the password from this environment variable
+    #[arg(long, value_name = "VAR")]
+    password_env: Option<String>,
+    /// Read the password from the first line of this file
+    #[arg(long, value_name = "PATH")]
+    password_file: Option<PathBuf>,
+    /// Use a raw 256-bit key file (32 bytes, or 64 hex digits) instead of a password
+    #[arg(long, value_name = "PATH")]
+    key_file: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ModeArg {
+    /// EAX authenticated encryption
+    Eax,
+    /// Unauthenticated CBC with PKCS#7 padding
+    Cbc,
+    /// Segmented EAX with a tag per 64 KiB, processed on multiple threads
+    Segmented,
+}
+impl From<ModeArg> for StreamMode {
+    fn from(mode: ModeArg) -> Self {
+        match mode {
+            ModeArg::Eax => StreamMode::Eax,
+            ModeArg::Cbc => StreamMode::Cbc,
+            ModeArg::Segmented => StreamMode::Segmented,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum KdfArg {
+    /// Argon2id, 19 MiB, 2 passes
+    Argon2id,
+    /// scrypt, N = 2^17, r = 8, p = 1
+    Scrypt,
+    /// PBKDF2-HMAC-SHA256, 100,000 iterations
+    Pbkdf2,
+}
+impl From<KdfArg> for Kdf {
+    fn from(kdf: KdfArg) -> Self {
+        match kdf {
+            KdfArg::Argon2id => Kdf::default(),
+            KdfArg::Scrypt => Kdf::SCRYPT_DEFAULT,
+            KdfArg::Pbkdf2 => Kdf::PBKDF2_DEFAULT,
+        }
+    }
+}
+
+// --- 密钥来源 ---
+enum Secret {
+    Password(Vec<u8>),
+    RawKey([u8; 32]),
+}
+
+fn read_secret(src: &KeySource, confirm: bool) -> CliResult<Secret> {
+    if let Some(path) = &src.key_file {
+        return Ok(Secret::RawKey(read_key_file(path)?));
+    }
+    let password = if let Some(var) = &src.password_env {
+        std::env::var(var).map_err(|_| format!("environment variable {} is not set", var))?
+    } else if let Some(path) = &src.password_file {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("cannot read password file {}: {}", path.display(), e))?;
+        text.lines().next().unwrap_or("").to_string()
+    } else {
+        let password = rpassword::prompt_password("Password: ")?;
+        if confirm && rpassword::prompt_password("Confirm password: ")? != password {
+            return Err("passwords do not match".into());
+        }
+        password
+    };
+    if password.is_empty() {
+        return Err("password is empty".into());
+    }
+    Ok(Secret::Password(password.into_bytes()))
+}
+
+// 原始密钥文件：32 字节二进制，或 64 个十六进制字符 (允许首尾空白)
+fn read_key_file(path: &Path) -> CliResult<[u8; 32]> {
+    let data =
+        fs::read(path).map_err(|e| format!("cannot read key file {}: {}", path.display(), e))?;
+    if let Ok(key) = <[u8; 32]>::try_from(data.as_slice()) {
+        return Ok(key);
+    }
+    let text = std::str::from_utf8(&data).map_err(|_| "key file is not 32 bytes or hex")?;
+    parse_hex(text.trim())
+        .ok_or_else(|| "key file must contain 32 raw bytes or 64 hex digits".into())
+}
+fn parse_hex<const N: usize>(hex: &str) -> Option<[u8; N]> {
+    if hex.len() != N * 2 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let mut out = [0u8; N];
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(out)
+}
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// --- 输入输出 ---
+fn open_input(path: Option<&Path>) -> CliResult<Box<dyn Read>> {
+    match path {
+        None => Ok(Box::new(io::stdin().lock())),
+        Some(p) if p.as_os_str() == "-" => Ok(Box::new(io::stdin().lock())),
+        Some(p) => {
+            let file = File::open(p).map_err(|e| format!("cannot open {}: {}", p.display(), e))?;
+            Ok(Box::new(BufReader::new(file)))
+        }
+    }
+}
+
+fn output_file(path: Option<&Path>) -> Option<&Path> {
+    path.filter(|p| p.as_os_str() != "-")
+}
+
+// private: 只允许属主读写 (密钥文件)
+#[cfg_attr(not(unix), allow(unused_variables))]
+fn open_output(path: Option<&Path>, force: bool, private: bool) -> CliResult<Box<dyn Write>> {
+    match output_file(path) {
+        None => Ok(Box::new(BufWriter::new(io::stdout().lock()))),
+        Some(p) => {
+            let mut options = OpenOptions::new();
+            options.write(true);
+            if force {
+                options.create(true).truncate(true);
+            } else {
+                options.create_new(true);
+            }
+            #[cfg(unix)]
+            if private {
+                use std::os::unix::fs::OpenOptionsExt;
+                options.mode(0o600);
+            }
+            let file = options.open(p).map_err(|e| {
+                if e.kind() == io::ErrorKind::AlreadyExists {
+                    format!("{} already exists (use --force to overwrite)", p.display())
+                } else {
+                    format!("cannot create {}: {}", p.display(), e)
+                }
+            })?;
+            // mode 只作用于新建的文件，--force 覆盖已有文件时也要收紧权限
+            #[cfg(unix)]
+            if private {
+                use std::os::unix::fs::PermissionsExt;
+                file.set_permissions(fs::Permissions::from_mode(0o600))?;
+            }
+            Ok(Box::new(BufWriter::new(file)))
+        }
+    }
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+// 打开输入输出后执行 op；失败时删除不完整的输出文件
+fn with_io(
+    args: &CryptArgs,
+    op: impl FnOnce(&mut dyn Read, &mut dyn Write) -> io::Result<()>,
+) -> CliResult<()> {
+    if let (Some(i), Some(o)) = (args.input.as_deref(), output_file(args.output.as_deref()))
+        && same_file(i, o)
+    {
+        return Err("input and output must be different files".into());
+    }
+    let mut reader = open_input(args.input.as_deref())?;
+    let mut writer = open_output(args.output.as_deref(), args.force, false)?;
+    let result = op(&mut reader, &mut writer).and_then(|_| writer.flush());
+    drop(writer);
+    if let Err(e) = result {
+        if let Some(p) = output_file(args.output.as_deref()) {
+            let _ = fs::remove_file(p);
+        }
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+// --- 子命令 ---
+fn run_encrypt(args: &CryptArgs) -> CliResult<()> {
+    let secret = read_secret(&args.key, true)?;
+    let mode = StreamMode::from(args.mode);
+    let options = StreamOptions {
+        mode,
+        kdf: Kdf::from(args.kdf),
+        threads: args.threads,
+    };
+    with_io(args, |mut reader, mut writer| match &secret {
+        Secret::Password(pw) => encrypt_stream_with_options(&mut reader, &mut writer, pw, &options),
+        Secret::RawKey(key) => {
+            let ctx = CipherCtx::new(key);
+            match mode {
+                StreamMode::Eax => seal_stream_with_ctx(&mut reader, &mut writer, &ctx, b""),
+                StreamMode::Cbc => encrypt_stream_with_ctx(&mut reader, &mut writer, &ctx),
+                StreamMode::Segmented => {
+                    seal_segmented_with_ctx(&mut reader, &mut writer, &ctx, b"", args.threads)
+                }
+            }
+        }
+    })
+}
+
+fn run_decrypt(args: &CryptArgs) -> CliResult<()> {
+    let secret = read_secret(&args.key, false)?;
+    let mode = StreamMode::from(args.mode);
+    with_io(args, |mut reader, mut writer| match &secret {
+        Secret::Password(pw) => decrypt_stream(&mut reader, &mut writer, pw),
+        Secret::RawKey(key) => {
+            let ctx = CipherCtx::new(key);
+            match mode {
+                StreamMode::Eax => open_stream_with_ctx(&mut reader, &mut writer, &ctx, b""),
+                StreamMode::Cbc => decrypt_stream_with_ctx(&mut reader, &mut writer, &ctx),
+                StreamMode::Segmented => {
+                    open_segmented_with_ctx(&mut reader, &mut writer, &ctx, b"", args.threads)
+                }
+            }
+        }
+    })
+}
+
+fn run_keygen(output: Option<&Path>, force: bool) -> CliResult<()> {
+    let mut key = [0u8; 32];
+    thread_rng().fill_bytes(&mut key);
+    match output_file(output) {
+        Some(_) => {
+            let mut writer = open_output(output, force, true)?;
+            writer.write_all(&key)?;
+            writer.flush()?;
+        }
+        None => println!("{}", to_hex(&key)),
+    }
+    Ok(())
+}
+
+fn report_throughput(label: &str, bytes: usize, secs: f64) {
+    println!(
+        "{:<28} {:>10.2} MB/s",
+        label,
+        bytes as f64 / secs / 1_000_000.0
+    );
+}
+
+fn run_bench(size_mib: usize) -> CliResult<()> {
+    let mut key = [0u8; 32];
+    thread_rng().fill_bytes(&mut key);
+
+    let iters = 2000;
+    let start = Instant::now();
+    for _ in 0..iters {
+        black_box(CipherCtx::new(black_box(&key)));
+    }
+    let per_op = start.elapsed().as_secs_f64() / iters as f64;
+    println!("{:<28} {:>10.2} µs", "Key schedule", per_op * 1e6);
+
+    let ctx = CipherCtx::new(&key);
+    let mut block = [0u8; 36];
+    thread_rng().fill_bytes(&mut block);
+    let blocks = 20_000;
+    let start = Instant::now();
+    for _ in 0..blocks {
+        block = encrypt_block_288(&ctx, black_box(&block));
+    }
+    report_throughput(
+        "Encrypt block (288)",
+        blocks * 36,
+        start.elapsed().as_secs_f64(),
+    );
+    let start = Instant::now();
+    for _ in 0..blocks {
+        block = decrypt_block_288(&ctx, black_box(&block));
+    }
+    report_throughput(
+        "Decrypt block (288)",
+        blocks * 36,
+        start.elapsed().as_secs_f64(),
+    );
+
+    let mut data = vec![0u8; size_mib * 1024 * 1024];
+    thread_rng().fill_bytes(&mut data);
+    for (name, mode) in [
+        ("EAX", StreamMode::Eax),
+        ("CBC", StreamMode::Cbc),
+        ("Segmented", StreamMode::Segmented),
+    ] {
+        let mut sealed = Vec::with_capacity(data.len() + 128);
+        let start = Instant::now();
+        match mode {
+            StreamMode::Eax => {
+                seal_stream_with_ctx(&mut Cursor::new(&data), &mut sealed, &ctx, b"")?
+            }
+            StreamMode::Cbc => encrypt_stream_with_ctx(&mut Cursor::new(&data), &mut sealed, &ctx)?,
+            StreamMode::Segmented => {
+                seal_segmented_with_ctx(&mut Cursor::new(&data), &mut sealed, &ctx, b"", 0)?
+            }
+        }
+        report_throughput(
+            &format!("Encrypt stream ({})", name),
+            data.len(),
+            start.elapsed().as_secs_f64(),
+        );
+        let mut opened = Vec::with_capacity(data.len());
+        let start = Instant::now();
+        match mode {
+            StreamMode::Eax => {
+                open_stream_with_ctx(&mut Cursor::new(&sealed), &mut opened, &ctx, b"")?
+            }
+            StreamMode::Cbc => {
+                decrypt_stream_with_ctx(&mut Cursor::new(&sealed), &mut opened, &ctx)?
+            }
+            StreamMode::Segmented => {
+                open_segmented_with_ctx(&mut Cursor::new(&sealed), &mut opened, &ctx, b"", 0)?
+            }
+        }
+        report_throughput(
+            &format!("Decrypt stream ({})", name),
+            data.len(),
+            start.elapsed().as_secs_f64(),
+        );
+    }
+    println!("For statistically rigorous numbers, use: `cargo bench`");
+    Ok(())
+}
+
+fn run_trace(key_file: Option<&Path>, block: Option<&str>) -> CliResult<()> {
+    let key = match key_file {
+        Some(path) => read_key_file(path)?,
+        None => [0u8; 32],
+    };
+    let pt: [u8; 36] = match block {
+        Some(hex) => parse_hex(hex.trim()).ok_or("block must be 72 hex digits (36 bytes)")?,
+        None => [0u8; 36],
+    };
+    let ctx = CipherCtx::new(&key);
+    let mut out = BufWriter::new(io::stdout().lock());
+    writeln!(out, "key        {}", to_hex(&key))?;
+    writeln!(out, "plaintext  {}", to_hex(&pt))?;
+    for round in trace_encrypt(&ctx, &pt) {
+        write!(out, "\n{}", round)?;
+    }
+    writeln!(out, "ciphertext {}", to_hex(&encrypt_block_288(&ctx, &pt)))?;
+    out.flush()?;
+    Ok(())
+}
+
+fn run_analyze(
+    trail_models: Option<&Path>,
+    linear_report: Option<&Path>,
+    trail_rounds: usize,
+    key_file: Option<&Path>,
+) -> CliResult<()> {
+    if !(1..=ROUNDS).contains(&trail_rounds) {
+        return Err(format!("--trail-rounds must be between 1 and {ROUNDS}").into());
+    }
+    let key = match key_file {
+        Some(path) => read_key_file(path)?,
+        None => analysis::ANALYSIS_KEY,
+    };
     println!("Running Security Analysis Suite...");
     println!("=================================");
-    
+
     // 调用分析模块的公共函数
     analysis::run();
+    if let Some(dir) = trail_models {
+        fs::create_dir_all(dir)?;
+        analysis::write_trail_models(dir, &key, trail_rounds)?;
+    }
+    if let Some(path) = linear_report {
+        analysis::write_linear_report(path, &key, trail_rounds)?;
+    }
 
     println!("=================================");
     println!("Analysis finished.");
-    println!("To run performance benchmarks, use: `cargo bench`");
-}
\ No newline at end of file
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match &cli.command {
+        Command::Encrypt(args) => run_encrypt(args),
+        Command::Decrypt(args) => run_decrypt(args),
+        Command::Keygen { output, force } => run_keygen(output.as_deref(), *force),
+        Command::Bench { size_mib } => run_bench(*size_mib),
+        Command::Trace { key_file, block } => run_trace(key_file.as_deref(), block.as_deref()),
+        Command::Analyze {
+            trail_models,
+            linear_report,
+            trail_rounds,
+            key_file,
+        } => run_analyze(
+            trail_models.as_deref(),
+            linear_report.as_deref(),
+            *trail_rounds,
+            key_file.as_deref(),
+        ),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,84 @@
+// --- 扇区加密 (类 XTS，保长度、可随机访问) ---
+//
+// 扇区按 36 字节切块，第 j 块用可调密码 WVX-T 加密，tweak 为
+//   扇区号 (u64 小端) || j (u64 小端)
+// 所以同一明文块在不同扇区、不同位置得到不同密文，且任意扇区可单独解密。
+// 扇区长度不是 36 的倍数时用 XTS 式的密文挪用：倒数第二块的密文尾部
+// 补齐最后的残块再加密一次，密文与明文等长。
+// 与 XTS 一样只保密不认证，篡改只会把对应块解成随机数据。
+use crate::BLOCK_SIZE;
+use crate::tweak::{TWEAK_SIZE, TweakableCtx, decrypt_block_tweak, encrypt_block_tweak};
+use std::io::{Error, ErrorKind, Result as IoResult};
+
+fn block_tweak(sector_no: u64, index: usize) -> [u8; TWEAK_SIZE] {
+    let mut tweak = [0u8; TWEAK_SIZE];
+    tweak[..8].copy_from_slice(&sector_no.to_le_bytes());
+    tweak[8..].copy_from_slice(&(index as u64).to_le_bytes());
+    tweak
+}
+fn check_len(len: usize) -> IoResult<()> {
+    if len < BLOCK_SIZE {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Sector must be at least one 36-byte block.",
+        ));
+    }
+    Ok(())
+}
+fn block_at(sector: &[u8], index: usize) -> [u8; BLOCK_SIZE] {
+    sector[index * BLOCK_SIZE..(index + 1) * BLOCK_SIZE]
+        .try_into()
+        .unwrap()
+}
+
+/// 就地加密一个扇区，长度不变；扇区至少 36 字节，其余长度任意。
+pub fn encrypt_sector(ctx: &TweakableCtx, sector_no: u64, sector: &mut [u8]) -> IoResult<()> {
+    check_len(sector.len())?;
+    let full = sector.len() / BLOCK_SIZE;
+    let rem = sector.len() % BLOCK_SIZE;
+    for j in 0..full {
+        let ct = encrypt_block_tweak(ctx, &block_tweak(sector_no, j), &block_at(sector, j));
+        sector[j * BLOCK_SIZE..(j + 1) * BLOCK_SIZE].copy_from_slice(&ct);
+    }
+    if rem > 0 {
+        // 密文挪用：末块密文的前 rem 字节成为残块密文，
+        // 残块明文补上末块密文的尾部后以下一个位置加密，放回末块位置
+        let last = (full - 1) * BLOCK_SIZE;
+        let mut pp = block_at(sector, full - 1);
+        let (head, tail) = sector.split_at_mut(full * BLOCK_SIZE);
+        let stolen = pp;
+        pp[..rem].copy_from_slice(tail);
+        tail.copy_from_slice(&stolen[..rem]);
+        let ct = encrypt_block_tweak(ctx, &block_tweak(sector_no, full), &pp);
+        head[last..].copy_from_slice(&ct);
+    }
+    Ok(())
+}
+
+/// 就地解密 `encrypt_sector` 的输出，`sector_no` 必须与加密时相同。
+pub fn decrypt_sector(ctx: &TweakableCtx, sector_no: u64, sector: &mut [u8]) -> IoResult<()> {
+    check_len(sector.len())?;
+    let full = sector.len() / BLOCK_SIZE;
+    let rem = sector.len() % BLOCK_SIZE;
+    // 有残块时末块是以位置 full 加密的，先还原出残块明文和原末块密文
+    let blocks = if rem > 0 { full - 1 } else { full };
+    if rem > 0 {
+        let last = (full - 1) * BLOCK_SIZE;
+        let pp = decrypt_block_tweak(
+            ctx,
+            &block_tweak(sector_no, full),
+            &block_at(sector, full - 1),
+        );
+        let (head, tail) = sector.split_at_mut(full * BLOCK_SIZE);
+        let mut cc = pp;
+        cc[..rem].copy_from_slice(tail);
+        tail.copy_from_slice(&pp[..rem]);
+        let pt = decrypt_block_tweak(ctx, &block_tweak(sector_no, full - 1), &cc);
+        head[last..].copy_from_slice(&pt);
+    }
+    for j in 0..blocks {
+        let pt = decrypt_block_tweak(ctx, &block_tweak(sector_no, j), &block_at(sector, j));
+        sector[j * BLOCK_SIZE..(j + 1) * BLOCK_SIZE].copy_from_slice(&pt);
+    }
+    Ok(())
+}
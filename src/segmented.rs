@@ -0,0 +1,398 @@
+// --- 分段认证加密 (STREAM 构造，可并行) ---
+//
+// 明文切成 SEGMENT_SIZE 字节的段，每段独立做 EAX：
+//   nonce_i = 前缀(19) || i (u32 大端) || last(1)
+// 输出为 `段_0 || 段_1 || ...`，每段为 `密文 || 标签(32)`；最后一段可为空但必须存在。
+// 计数器防止段被重排，last 标志防止在段边界截断或追加。
+// 段互不依赖，所以按窗口读入后在多个线程上并行处理；段的划分只取决于输入，
+// 因此输出与线程数无关。
+use crate::adapters::Encoder;
+use crate::aead::{self, NONCE_SIZE, TAG_SIZE};
+use crate::{CipherCtx, DecryptReader, EncryptWriter, StreamMode, read_full};
+use rand::{RngCore, thread_rng};
+use std::io::{Error, ErrorKind, Read, Result as IoResult, Write};
+
+/// 每段的明文字节数 (64 KiB)。
+pub const SEGMENT_SIZE: usize = 64 * 1024;
+/// 分段模式 nonce 前缀的长度，其余5字节为段计数器和末段标志。
+pub const SEGMENT_PREFIX_SIZE: usize = NONCE_SIZE - 5;
+// 每个线程每个窗口处理的段数
+const SEGMENTS_PER_WORKER: usize = 4;
+
+pub(crate) fn segment_nonce(
+    prefix: &[u8; SEGMENT_PREFIX_SIZE],
+    index: u64,
+    last: bool,
+) -> IoResult<[u8; NONCE_SIZE]> {
+    let index = u32::try_from(index).map_err(|_| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            "Too many segments for one nonce prefix.",
+        )
+    })?;
+    let mut nonce = [0u8; NONCE_SIZE];
+    nonce[..SEGMENT_PREFIX_SIZE].copy_from_slice(prefix);
+    nonce[SEGMENT_PREFIX_SIZE..NONCE_SIZE - 1].copy_from_slice(&index.to_be_bytes());
+    nonce[NONCE_SIZE - 1] = last as u8;
+    Ok(nonce)
+}
+// 0 表示使用全部可用核心
+fn worker_count(threads: usize) -> usize {
+    match threads {
+        0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    }
+}
+// 把任务切成至多 workers 个连续区间并行执行
+fn parallel_for<T: Send>(tasks: &mut [T], workers: usize, f: impl Fn(&mut T) + Sync) {
+    if workers <= 1 || tasks.len() <= 1 {
+        tasks.iter_mut().for_each(f);
+        return;
+    }
+    let per_worker = tasks.len().div_ceil(workers);
+    let f = &f;
+    std::thread::scope(|s| {
+        for group in tasks.chunks_mut(per_worker) {
+            s.spawn(move || group.iter_mut().for_each(f));
+        }
+    });
+}
+// 读满一个窗口；用1字节前瞻区分“恰好读满”与“输入结束”，返回是否为最后一个窗口
+fn read_window(
+    reader: &mut impl Read,
+    carry: &mut Option<u8>,
+    window: &mut Vec<u8>,
+    size: usize,
+) -> IoResult<bool> {
+    window.clear();
+    window.extend(carry.take());
+    let start = window.len();
+    window.resize(size, 0);
+    let filled = start + read_full(reader, &mut window[start..])?;
+    window.truncate(filled);
+    if filled < size {
+        return Ok(true);
+    }
+    let mut peek = [0u8; 1];
+    if read_full(reader, &mut peek)? == 0 {
+        return Ok(true);
+    }
+    *carry = Some(peek[0]);
+    Ok(false)
+}
+
+/// 以随机 nonce 前缀分段加密，输出 `前缀(19) || 段...`。
+///
+/// `threads` 为工作线程数，0 表示使用全部可用核心；输出与线程数无关。
+pub fn seal_segmented_with_ctx(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    ctx: &CipherCtx,
+    ad: &[u8],
+    threads: usize,
+) -> IoResult<()> {
+    let mut prefix = [0u8; SEGMENT_PREFIX_SIZE];
+    thread_rng().fill_bytes(&mut prefix);
+    seal_segmented_with_prefix(reader, writer, ctx, &prefix, ad, threads)
+}
+/// 与 [`seal_segmented_with_ctx`] 相同，但使用调用者给定的 nonce 前缀。
+///
+/// 同一密钥下前缀绝不能重复使用。
+pub fn seal_segmented_with_prefix(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    ctx: &CipherCtx,
+    prefix: &[u8; SEGMENT_PREFIX_SIZE],
+    ad: &[u8],
+    threads: usize,
+) -> IoResult<()> {
+    ctx.assert_full_strength();
+    writer.write_all(prefix)?;
+    seal_segmented_body(reader, writer, ctx, prefix, ad, threads)
+}
+pub(crate) fn seal_segmented_body(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    ctx: &CipherCtx,
+    prefix: &[u8; SEGMENT_PREFIX_SIZE],
+    ad: &[u8],
+    threads: usize,
+) -> IoResult<()> {
+    let workers = worker_count(threads);
+    let window_segments = workers * SEGMENTS_PER_WORKER;
+    let mut carry = None;
+    let mut window = Vec::new();
+    let mut out = Vec::new();
+    let mut index = 0u64;
+    loop {
+        let last_window = read_window(
+            reader,
+            &mut carry,
+            &mut window,
+            window_segments * SEGMENT_SIZE,
+        )?;
+        // 每段在输出中占 SEGMENT_SIZE + TAG_SIZE 字节，末段为空时只有标签
+        let count = window.len().div_ceil(SEGMENT_SIZE).max(1);
+        out.clear();
+        out.resize(window.len() + count * TAG_SIZE, 0);
+        let mut tasks = Vec::with_capacity(count);
+        for (k, (seg, pt)) in out
+            .chunks_mut(SEGMENT_SIZE + TAG_SIZE)
+            .zip(window.chunks(SEGMENT_SIZE).chain(std::iter::once(&[][..])))
+            .enumerate()
+        {
+            seg[..pt.len()].copy_from_slice(pt);
+            let nonce = segment_nonce(prefix, index + k as u64, last_window && k + 1 == count)?;
+            tasks.push((nonce, seg));
+        }
+        parallel_for(&mut tasks, workers, |(nonce, seg)| {
+            let len = seg.len() - TAG_SIZE;
+            let tag = aead::seal_in_place_detached(ctx, nonce, ad, &mut seg[..len]);
+            seg[len..].copy_from_slice(&tag);
+        });
+        writer.write_all(&out)?;
+        if last_window {
+            return Ok(());
+        }
+        index += count as u64;
+    }
+}
+
+/// 解密 [`seal_segmented_with_ctx`] 的输出。
+///
+/// 各段并行校验；一个窗口内的所有段都通过校验后才写出该窗口的明文。
+/// 段被篡改、重排、截断或追加时返回错误，此前已写出的明文均来自已认证的段。
+pub fn open_segmented_with_ctx(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    ctx: &CipherCtx,
+    ad: &[u8],
+    threads: usize,
+) -> IoResult<()> {
+    ctx.assert_full_strength();
+    let mut prefix = [0u8; SEGMENT_PREFIX_SIZE];
+    reader.read_exact(&mut prefix)?;
+    open_segmented_body(reader, writer, ctx, &prefix, ad, threads)
+}
+pub(crate) fn open_segmented_body(
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    ctx: &CipherCtx,
+    prefix: &[u8; SEGMENT_PREFIX_SIZE],
+    ad: &[u8],
+    threads: usize,
+) -> IoResult<()> {
+    let workers = worker_count(threads);
+    let window_segments = workers * SEGMENTS_PER_WORKER;
+    let mut carry = None;
+    let mut window = Vec::new();
+    let mut index = 0u64;
+    loop {
+        let last_window = read_window(
+            reader,
+            &mut carry,
+            &mut window,
+            window_segments * (SEGMENT_SIZE + TAG_SIZE),
+        )?;
+        let count = window.len().div_ceil(SEGMENT_SIZE + TAG_SIZE);
+        if count == 0 || window.len() - (count - 1) * (SEGMENT_SIZE + TAG_SIZE) < TAG_SIZE {
+            return Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                "Segmented ciphertext is truncated.",
+            ));
+        }
+        let mut tasks = Vec::with_capacity(count);
+        for (k, seg) in window.chunks_mut(SEGMENT_SIZE + TAG_SIZE).enumerate() {
+            let nonce = segment_nonce(prefix, index + k as u64, last_window && k + 1 == count)?;
+            tasks.push((nonce, seg, Ok(())));
+        }
+        parallel_for(&mut tasks, workers, |(nonce, seg, result)| {
+            let (ct, tag) = seg.split_at_mut(seg.len() - TAG_SIZE);
+            *result = aead::open_in_place_detached(ctx, nonce, ad, ct, (&*tag).try_into().unwrap());
+        });
+        for (_, _, result) in &tasks {
+            (*result)?;
+        }
+        for (_, seg, _) in &tasks {
+            writer.write_all(&seg[..seg.len() - TAG_SIZE])?;
+        }
+        if last_window {
+            return Ok(());
+        }
+        index += count as u64;
+    }
+}
+
+// 在线加密核心 (无 I/O)：段满后要等到确实还有数据时才加密，因为最后一段须带末段标志
+pub(crate) struct SegmentSealer {
+    ctx: CipherCtx,
+    ad: Vec<u8>,
+    prefix: [u8; SEGMENT_PREFIX_SIZE],
+    prefix_written: bool,
+    index: u64,
+    buf: Vec<u8>,
+}
+impl SegmentSealer {
+    pub(crate) fn new(ctx: CipherCtx, prefix: &[u8; SEGMENT_PREFIX_SIZE], ad: &[u8]) -> Self {
+        SegmentSealer {
+            ctx,
+            ad: ad.to_vec(),
+            prefix: *prefix,
+            prefix_written: false,
+            index: 0,
+            buf: Vec::with_capacity(SEGMENT_SIZE),
+        }
+    }
+    fn seal_buffered(&mut self, last: bool, out: &mut Vec<u8>) -> IoResult<()> {
+        if !self.prefix_written {
+            out.extend_from_slice(&self.prefix);
+            self.prefix_written = true;
+        }
+        let nonce = segment_nonce(&self.prefix, self.index, last)?;
+        let tag = aead::seal_in_place_detached(&self.ctx, &nonce, &self.ad, &mut self.buf);
+        out.extend_from_slice(&self.buf);
+        out.extend_from_slice(&tag);
+        self.buf.clear();
+        self.index += 1;
+        Ok(())
+    }
+    // 每次最多写出一段，返回消耗的明文字节数
+    pub(crate) fn update(&mut self, data: &[u8], out: &mut Vec<u8>) -> IoResult<usize> {
+        if data.is_empty() {
+            return Ok(0);
+        }
+        if self.buf.len() == SEGMENT_SIZE {
+            self.seal_buffered(false, out)?;
+        }
+        let n = data.len().min(SEGMENT_SIZE - self.buf.len());
+        self.buf.extend_from_slice(&data[..n]);
+        Ok(n)
+    }
+    pub(crate) fn finish(&mut self, out: &mut Vec<u8>) -> IoResult<()> {
+        self.seal_buffered(true, out)
+    }
+}
+
+// 在线解密核心 (无 I/O)：缓冲区超过一整段时，开头那段必然不是最后一段
+pub(crate) struct SegmentOpener {
+    ctx: CipherCtx,
+    ad: Vec<u8>,
+    prefix: Option<[u8; SEGMENT_PREFIX_SIZE]>,
+    index: u64,
+    buf: Vec<u8>,
+}
+impl SegmentOpener {
+    pub(crate) fn new(ctx: CipherCtx, ad: &[u8]) -> Self {
+        SegmentOpener {
+            ctx,
+            ad: ad.to_vec(),
+            prefix: None,
+            index: 0,
+            buf: Vec::with_capacity(2 * (SEGMENT_SIZE + TAG_SIZE)),
+        }
+    }
+    fn open_segment(&mut self, len: usize, last: bool, out: &mut Vec<u8>) -> IoResult<()> {
+        let prefix = self.prefix.as_ref().unwrap();
+        let nonce = segment_nonce(prefix, self.index, last)?;
+        let (ct, tag) = self.buf[..len].split_at_mut(len - TAG_SIZE);
+        aead::open_in_place_detached(&self.ctx, &nonce, &self.ad, ct, (&*tag).try_into().unwrap())?;
+        out.extend_from_slice(ct);
+        self.buf.drain(..len);
+        self.index += 1;
+        Ok(())
+    }
+    // 写出所有已能确定不是最后一段的段
+    pub(crate) fn update(&mut self, data: &[u8], out: &mut Vec<u8>) -> IoResult<()> {
+        self.buf.extend_from_slice(data);
+        if self.prefix.is_none() {
+            if self.buf.len() < SEGMENT_PREFIX_SIZE {
+                return Ok(());
+            }
+            self.prefix = Some(self.buf[..SEGMENT_PREFIX_SIZE].try_into().unwrap());
+            self.buf.drain(..SEGMENT_PREFIX_SIZE);
+        }
+        while self.buf.len() > SEGMENT_SIZE + TAG_SIZE {
+            self.open_segment(SEGMENT_SIZE + TAG_SIZE, false, out)?;
+        }
+        Ok(())
+    }
+    // 输入结束：剩余部分必须恰好是带末段标志的最后一段
+    pub(crate) fn finish(&mut self, out: &mut Vec<u8>) -> IoResult<()> {
+        if self.prefix.is_none() || self.buf.len() < TAG_SIZE {
+            return Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                "Segmented ciphertext is truncated.",
+            ));
+        }
+        self.open_segment(self.buf.len(), true, out)
+    }
+}
+
+/// 分段格式的在线加密器：实现 `Write`，每攒满一段就加密写出。
+///
+/// 输出与 [`seal_segmented_with_prefix`] 逐字节相同。必须调用 [`finish`](Self::finish)
+/// 写出带末段标志的最后一段；未调用 `finish` 就丢弃会得到无法通过校验的截断密文。
+pub struct SegmentedWriter<W: Write>(EncryptWriter<W>);
+impl<W: Write> SegmentedWriter<W> {
+    /// 以随机 nonce 前缀创建加密器。
+    pub fn new(inner: W, ctx: CipherCtx, ad: &[u8]) -> Self {
+        SegmentedWriter(EncryptWriter::with_ad(
+            inner,
+            ctx,
+            StreamMode::Segmented,
+            ad,
+        ))
+    }
+    /// 使用调用者给定的 nonce 前缀；同一密钥下前缀绝不能重复使用。
+    pub fn with_prefix(
+        inner: W,
+        ctx: CipherCtx,
+        prefix: &[u8; SEGMENT_PREFIX_SIZE],
+        ad: &[u8],
+    ) -> Self {
+        ctx.assert_full_strength();
+        let sealer = SegmentSealer::new(ctx, prefix, ad);
+        SegmentedWriter(EncryptWriter::from_encoder(
+            inner,
+            Encoder::Segmented(Box::new(sealer)),
+        ))
+    }
+    /// 写出最后一段并返回内部写入器。
+    pub fn finish(self) -> IoResult<W> {
+        self.0.finish()
+    }
+}
+impl<W: Write> Write for SegmentedWriter<W> {
+    fn write(&mut self, data: &[u8]) -> IoResult<usize> {
+        self.0.write(data)
+    }
+    /// 只刷新内部写入器；未满的段要到写满或 `finish` 时才会写出。
+    fn flush(&mut self) -> IoResult<()> {
+        self.0.flush()
+    }
+}
+
+/// 分段格式的在线解密器：实现 `Read`，每段校验通过后才交出该段明文。
+///
+/// 段被篡改、重排、截断或追加时 `read` 返回错误，且之后的每次调用都返回错误；
+/// 只有读到带末段标志的最后一段后才会返回 `Ok(0)`。
+pub struct SegmentedReader<R: Read>(DecryptReader<R>);
+impl<R: Read> SegmentedReader<R> {
+    pub fn new(inner: R, ctx: CipherCtx, ad: &[u8]) -> Self {
+        SegmentedReader(DecryptReader::with_ad(
+            inner,
+            ctx,
+            StreamMode::Segmented,
+            ad,
+        ))
+    }
+    /// 返回内部读取器。
+    pub fn into_inner(self) -> R {
+        self.0.into_inner()
+    }
+}
+impl<R: Read> Read for SegmentedReader<R> {
+    fn read(&mut self, out: &mut [u8]) -> IoResult<usize> {
+        self.0.read(out)
+    }
+}
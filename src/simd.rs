@@ -0,0 +1,189 @@
+// --- 位切片引擎的 SIMD Lane (`simd` 特性) ---
+//
+// 位切片后，列 MDS、9 位循环移位、stream_fwd / vtx_shuffle 和 S-box 电路都只是
+// 位平面上的与/异或/换线。把 Lane 换成 SIMD 寄存器，每条指令同时处理
+// 128 (SSE2 / NEON) 或 256 (AVX2) 个块。入口函数带 `target_feature`，
+// 泛型轮函数 (`#[inline(always)]`) 在其中展开并编译为向量指令。
+#[cfg(target_arch = "x86_64")]
+pub(crate) use x86::{
+    decrypt_blocks_avx2, decrypt_blocks_sse2, encrypt_blocks_avx2, encrypt_blocks_sse2,
+};
+
+#[cfg(target_arch = "aarch64")]
+pub(crate) use neon::{decrypt_blocks_neon, encrypt_blocks_neon};
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use crate::bitslice::{BlockLane, Lane, decrypt_blocks_lanes, encrypt_blocks_lanes};
+    use crate::{BLOCK_SIZE, CipherCtx};
+    use std::arch::x86_64::*;
+    use std::ops::{BitAnd, BitXor, Not};
+
+    #[derive(Clone, Copy)]
+    pub(crate) struct Sse2(__m128i);
+    impl BitAnd for Sse2 {
+        type Output = Self;
+        #[inline(always)]
+        fn bitand(self, rhs: Self) -> Self {
+            Sse2(unsafe { _mm_and_si128(self.0, rhs.0) })
+        }
+    }
+    impl BitXor for Sse2 {
+        type Output = Self;
+        #[inline(always)]
+        fn bitxor(self, rhs: Self) -> Self {
+            Sse2(unsafe { _mm_xor_si128(self.0, rhs.0) })
+        }
+    }
+    impl Not for Sse2 {
+        type Output = Self;
+        #[inline(always)]
+        fn not(self) -> Self {
+            Sse2(unsafe { _mm_xor_si128(self.0, _mm_set1_epi64x(-1)) })
+        }
+    }
+    impl Lane for Sse2 {
+        const ZERO: Self = Sse2(unsafe { std::mem::transmute::<[u64; 2], __m128i>([0; 2]) });
+        #[inline(always)]
+        fn mask(bit: u16) -> Self {
+            Sse2(unsafe { _mm_set1_epi64x(-((bit & 1) as i64)) })
+        }
+    }
+    impl BlockLane for Sse2 {
+        const WORDS: usize = 2;
+        #[inline(always)]
+        fn from_words(words: &[u64]) -> Self {
+            assert_eq!(words.len(), Self::WORDS);
+            Sse2(unsafe { _mm_loadu_si128(words.as_ptr() as *const __m128i) })
+        }
+        #[inline(always)]
+        fn to_words(self, words: &mut [u64]) {
+            assert_eq!(words.len(), Self::WORDS);
+            unsafe { _mm_storeu_si128(words.as_mut_ptr() as *mut __m128i, self.0) }
+        }
+    }
+
+    #[derive(Clone, Copy)]
+    pub(crate) struct Avx2(__m256i);
+    impl BitAnd for Avx2 {
+        type Output = Self;
+        #[inline(always)]
+        fn bitand(self, rhs: Self) -> Self {
+            Avx2(unsafe { _mm256_and_si256(self.0, rhs.0) })
+        }
+    }
+    impl BitXor for Avx2 {
+        type Output = Self;
+        #[inline(always)]
+        fn bitxor(self, rhs: Self) -> Self {
+            Avx2(unsafe { _mm256_xor_si256(self.0, rhs.0) })
+        }
+    }
+    impl Not for Avx2 {
+        type Output = Self;
+        #[inline(always)]
+        fn not(self) -> Self {
+            Avx2(unsafe { _mm256_xor_si256(self.0, _mm256_set1_epi64x(-1)) })
+        }
+    }
+    impl Lane for Avx2 {
+        const ZERO: Self = Avx2(unsafe { std::mem::transmute::<[u64; 4], __m256i>([0; 4]) });
+        #[inline(always)]
+        fn mask(bit: u16) -> Self {
+            Avx2(unsafe { _mm256_set1_epi64x(-((bit & 1) as i64)) })
+        }
+    }
+    impl BlockLane for Avx2 {
+        const WORDS: usize = 4;
+        #[inline(always)]
+        fn from_words(words: &[u64]) -> Self {
+            assert_eq!(words.len(), Self::WORDS);
+            Avx2(unsafe { _mm256_loadu_si256(words.as_ptr() as *const __m256i) })
+        }
+        #[inline(always)]
+        fn to_words(self, words: &mut [u64]) {
+            assert_eq!(words.len(), Self::WORDS);
+            unsafe { _mm256_storeu_si256(words.as_mut_ptr() as *mut __m256i, self.0) }
+        }
+    }
+
+    // SSE2 是 x86-64 的基线指令集，无需运行时检测
+    pub(crate) fn encrypt_blocks_sse2(ctx: &CipherCtx, blocks: &mut [[u8; BLOCK_SIZE]]) {
+        encrypt_blocks_lanes::<Sse2>(ctx, blocks)
+    }
+    pub(crate) fn decrypt_blocks_sse2(ctx: &CipherCtx, blocks: &mut [[u8; BLOCK_SIZE]]) {
+        decrypt_blocks_lanes::<Sse2>(ctx, blocks)
+    }
+    /// # Safety
+    /// 调用者须确认 CPU 支持 AVX2。
+    #[target_feature(enable = "avx2")]
+    pub(crate) unsafe fn encrypt_blocks_avx2(ctx: &CipherCtx, blocks: &mut [[u8; BLOCK_SIZE]]) {
+        encrypt_blocks_lanes::<Avx2>(ctx, blocks)
+    }
+    /// # Safety
+    /// 调用者须确认 CPU 支持 AVX2。
+    #[target_feature(enable = "avx2")]
+    pub(crate) unsafe fn decrypt_blocks_avx2(ctx: &CipherCtx, blocks: &mut [[u8; BLOCK_SIZE]]) {
+        decrypt_blocks_lanes::<Avx2>(ctx, blocks)
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod neon {
+    use crate::bitslice::{BlockLane, Lane, decrypt_blocks_lanes, encrypt_blocks_lanes};
+    use crate::{BLOCK_SIZE, CipherCtx};
+    use std::arch::aarch64::*;
+    use std::ops::{BitAnd, BitXor, Not};
+
+    #[derive(Clone, Copy)]
+    pub(crate) struct Neon(uint64x2_t);
+    impl BitAnd for Neon {
+        type Output = Self;
+        #[inline(always)]
+        fn bitand(self, rhs: Self) -> Self {
+            Neon(unsafe { vandq_u64(self.0, rhs.0) })
+        }
+    }
+    impl BitXor for Neon {
+        type Output = Self;
+        #[inline(always)]
+        fn bitxor(self, rhs: Self) -> Self {
+            Neon(unsafe { veorq_u64(self.0, rhs.0) })
+        }
+    }
+    impl Not for Neon {
+        type Output = Self;
+        #[inline(always)]
+        fn not(self) -> Self {
+            Neon(unsafe { veorq_u64(self.0, vdupq_n_u64(u64::MAX)) })
+        }
+    }
+    impl Lane for Neon {
+        const ZERO: Self = Neon(unsafe { std::mem::transmute::<[u64; 2], uint64x2_t>([0; 2]) });
+        #[inline(always)]
+        fn mask(bit: u16) -> Self {
+            Neon(unsafe { vdupq_n_u64(0u64.wrapping_sub((bit & 1) as u64)) })
+        }
+    }
+    impl BlockLane for Neon {
+        const WORDS: usize = 2;
+        #[inline(always)]
+        fn from_words(words: &[u64]) -> Self {
+            assert_eq!(words.len(), Self::WORDS);
+            Neon(unsafe { vld1q_u64(words.as_ptr()) })
+        }
+        #[inline(always)]
+        fn to_words(self, words: &mut [u64]) {
+            assert_eq!(words.len(), Self::WORDS);
+            unsafe { vst1q_u64(words.as_mut_ptr(), self.0) }
+        }
+    }
+
+    // AArch64 目标默认启用 NEON
+    pub(crate) fn encrypt_blocks_neon(ctx: &CipherCtx, blocks: &mut [[u8; BLOCK_SIZE]]) {
+        encrypt_blocks_lanes::<Neon>(ctx, blocks)
+    }
+    pub(crate) fn decrypt_blocks_neon(ctx: &CipherCtx, blocks: &mut [[u8; BLOCK_SIZE]]) {
+        decrypt_blocks_lanes::<Neon>(ctx, blocks)
+    }
+}
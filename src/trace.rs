@@ -0,0 +1,113 @@
+// --- 轮函数中间状态跟踪 ---
+//
+// 跟踪与加密走同一个轮函数 (查表实现或 constant_time 实现)，
+// 只是在每一步之后多记录一次状态，所以结果与实际加密逐位一致。
+use crate::{CELLS, COLS, CipherCtx, ROWS, encrypt_round_observed, unpack_state};
+use std::fmt;
+
+/// 轮函数的一个步骤，按执行顺序排列。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundStep {
+    /// 异或轮密钥掩码。
+    SubKeyXor,
+    /// S 盒代换并按列做 MDS 混合 (查表实现中两者融合为一步)。
+    SubCellsMds,
+    /// 每个 cell 循环左移 1 位。
+    BitRotate,
+    /// 按方向置换把各比特流向相邻 cell。
+    StreamFwd,
+    /// 整个网格按轮位移平移。
+    VtxShuffle,
+}
+impl RoundStep {
+    pub const ALL: [RoundStep; 5] = [
+        RoundStep::SubKeyXor,
+        RoundStep::SubCellsMds,
+        RoundStep::BitRotate,
+        RoundStep::StreamFwd,
+        RoundStep::VtxShuffle,
+    ];
+    pub fn name(self) -> &'static str {
+        match self {
+            RoundStep::SubKeyXor => "SubKeyXOR",
+            RoundStep::SubCellsMds => "SubCells+MDS",
+            RoundStep::BitRotate => "BitRotate",
+            RoundStep::StreamFwd => "StreamFwd",
+            RoundStep::VtxShuffle => "VtxShuffle",
+        }
+    }
+}
+impl fmt::Display for RoundStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// 一轮的跟踪记录：输入状态和每一步之后的状态 (按 [`RoundStep::ALL`] 的顺序)。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundTrace {
+    /// 轮号，从 1 开始。
+    pub round: usize,
+    pub input: [u16; 32],
+    pub steps: [[u16; 32]; 5],
+}
+impl RoundTrace {
+    /// 指定步骤之后的状态。
+    pub fn after(&self, step: RoundStep) -> &[u16; 32] {
+        &self.steps[step as usize]
+    }
+    /// 本轮输出，即 VtxShuffle 之后的状态。
+    pub fn output(&self) -> &[u16; 32] {
+        self.after(RoundStep::VtxShuffle)
+    }
+}
+impl fmt::Display for RoundTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Round {}", self.round)?;
+        writeln!(f, "  input")?;
+        write_grid(f, &self.input)?;
+        for (step, cells) in RoundStep::ALL.iter().zip(&self.steps) {
+            writeln!(f, "  {step}")?;
+            write_grid(f, cells)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_grid(f: &mut impl fmt::Write, cells: &[u16; CELLS]) -> fmt::Result {
+    for row in cells.chunks(COLS) {
+        f.write_str("   ")?;
+        for cell in row {
+            write!(f, " {cell:03x}")?;
+        }
+        f.write_char('\n')?;
+    }
+    Ok(())
+}
+/// 把状态排成 4x8 网格，每个 9 位 cell 显示为 3 位十六进制，每行以换行结尾。
+pub fn format_grid(cells: &[u16; 32]) -> String {
+    let mut out = String::with_capacity(ROWS * (3 + COLS * 4 + 1));
+    write_grid(&mut out, cells).unwrap();
+    out
+}
+
+/// 加密一个块并记录每轮每一步之后的状态；最后一轮的输出打包后即为密文。
+pub fn trace_encrypt(ctx: &CipherCtx, pt: &[u8; 36]) -> Vec<RoundTrace> {
+    let mut cells = unpack_state(pt);
+    ctx.active_rounds()
+        .iter()
+        .enumerate()
+        .map(|(r, rk)| {
+            let input = cells;
+            let mut steps = [[0u16; CELLS]; 5];
+            encrypt_round_observed(&mut cells, rk, ctx.layers(), |step, state| {
+                steps[step as usize] = *state;
+            });
+            RoundTrace {
+                round: r + 1,
+                input,
+                steps,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,294 @@
+// --- 最少活跃 S 盒模型 (0-1 整数规划，CPLEX LP 格式) ---
+//
+// 以 cell 为单位的截断差分，x 为每轮 S 盒输入的活跃 cell，目标是最小化活跃 S 盒总数：
+// - S 盒是双射，输入活跃当且仅当输出活跃；
+// - MDS 按列用分支数 B 建模：一列有活跃 cell 时输入与输出活跃数之和至少为 B，
+//   且 MDS 可逆，输入和输出各至少一个活跃 (B 小于 ROWS + 1 时这一条不能由分支数推出)；
+// - MDS 输出的活跃 cell 可以是任意非零比特模式，BitRotate、StreamFwd、VtxShuffle
+//   都是比特置换，逐比特跟到下一轮：下一轮的 cell 活跃当且仅当流入它的某一比特活跃。
+// StreamFwd 的方向置换和 VtxShuffle 的位移由轮密钥决定，所以模型针对给定密钥，
+// 最优值是该密钥下任一差分特征活跃 S 盒数的下界。
+// 线性模型结构相同，变量表示掩码非零，MDS 换用转置矩阵的分支数 (即线性分支数)。
+use crate::{CELLS, COLS, CipherCtx, Layers, MDS_ROW, ROWS, RoundKey, VEC, gf_mul};
+use std::io::{Result as IoResult, Write};
+
+// LP 文件中每行最多写出的项数
+const TERMS_PER_LINE: usize = 8;
+
+#[derive(Clone, Copy)]
+enum Sense {
+    Ge,
+    Le,
+}
+struct Constraint {
+    terms: Vec<(i32, usize)>,
+    sense: Sense,
+    rhs: i32,
+}
+
+/// 0-1 整数规划：最小化若干变量之和，约束均为线性不等式。
+pub struct TrailModel {
+    title: String,
+    names: Vec<String>,
+    objective: Vec<usize>,
+    constraints: Vec<Constraint>,
+}
+impl TrailModel {
+    fn new(title: String) -> Self {
+        Self {
+            title,
+            names: Vec::new(),
+            objective: Vec::new(),
+            constraints: Vec::new(),
+        }
+    }
+    fn var(&mut self, name: String) -> usize {
+        self.names.push(name);
+        self.names.len() - 1
+    }
+    fn add(&mut self, terms: Vec<(i32, usize)>, sense: Sense, rhs: i32) {
+        self.constraints.push(Constraint { terms, sense, rhs });
+    }
+    pub fn num_variables(&self) -> usize {
+        self.names.len()
+    }
+    pub fn num_constraints(&self) -> usize {
+        self.constraints.len()
+    }
+    /// 按名称查找变量下标，命名规则见 [`differential_model`]。
+    pub fn variable(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|n| n == name)
+    }
+    /// 一组取值 (按变量下标) 是否满足全部约束。
+    pub fn is_feasible(&self, values: &[bool]) -> bool {
+        assert_eq!(values.len(), self.names.len());
+        self.constraints.iter().all(|c| {
+            let lhs: i32 = c.terms.iter().map(|&(a, v)| a * values[v] as i32).sum();
+            match c.sense {
+                Sense::Ge => lhs >= c.rhs,
+                Sense::Le => lhs <= c.rhs,
+            }
+        })
+    }
+    /// 目标函数值，即活跃 S 盒数。
+    pub fn objective_value(&self, values: &[bool]) -> usize {
+        self.objective.iter().filter(|&&v| values[v]).count()
+    }
+    /// 以 CPLEX LP 格式写出，HiGHS、CBC、SCIP 和 `glpsol --lp` 都能直接读取。
+    pub fn write_lp(&self, w: &mut impl Write) -> IoResult<()> {
+        writeln!(w, "\\ {}", self.title)?;
+        writeln!(w, "Minimize")?;
+        write!(w, " active:")?;
+        self.write_terms(w, self.objective.iter().map(|&v| (1, v)))?;
+        writeln!(w)?;
+        writeln!(w, "Subject To")?;
+        for (n, c) in self.constraints.iter().enumerate() {
+            write!(w, " c{n}:")?;
+            self.write_terms(w, c.terms.iter().copied())?;
+            let op = match c.sense {
+                Sense::Ge => ">=",
+                Sense::Le => "<=",
+            };
+            writeln!(w, " {op} {}", c.rhs)?;
+        }
+        writeln!(w, "Binary")?;
+        for chunk in self.names.chunks(TERMS_PER_LINE) {
+            writeln!(w, " {}", chunk.join(" "))?;
+        }
+        writeln!(w, "End")
+    }
+    fn write_terms(
+        &self,
+        w: &mut impl Write,
+        terms: impl Iterator<Item = (i32, usize)>,
+    ) -> IoResult<()> {
+        for (n, (coef, v)) in terms.enumerate() {
+            if n > 0 && n % TERMS_PER_LINE == 0 {
+                write!(w, "\n   ")?;
+            }
+            let sign = if coef < 0 { '-' } else { '+' };
+            match coef.abs() {
+                1 => write!(w, " {sign} {}", self.names[v])?,
+                a => write!(w, " {sign} {a} {}", self.names[v])?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 为 `ctx` 的轮数、层开关和轮密钥建立最少活跃 S 盒的差分模型。
+///
+/// 变量 (轮号 r 从 0 起)：`x_r_i` 第 r 轮 S 盒 i 活跃；`y_r_i` MDS 输出 cell i 活跃；
+/// `d_r_c` 第 c 列活跃；`b_r_i_k` MDS 输出 cell i 的第 k 位有差分。
+/// 最后一轮的扩散不影响目标，轮间约束只建到倒数第二轮。
+pub fn differential_model(ctx: &CipherCtx) -> TrailModel {
+    build_model("differential", ctx, mds_branch_number())
+}
+/// 最少活跃 S 盒的线性模型，变量命名与 [`differential_model`] 相同，表示掩码非零。
+pub fn linear_model(ctx: &CipherCtx) -> TrailModel {
+    build_model("linear", ctx, mds_linear_branch_number())
+}
+
+fn build_model(kind: &str, ctx: &CipherCtx, branch: usize) -> TrailModel {
+    let rounds = ctx.active_rounds();
+    assert!(!rounds.is_empty(), "trail model needs at least one round");
+    let layers = ctx.layers();
+    let mut m = TrailModel::new(format!(
+        "WVX {kind} trail model: {} rounds, {layers:?}, MDS branch number {branch}",
+        rounds.len()
+    ));
+    let mut x: Vec<usize> = (0..CELLS).map(|i| m.var(format!("x_0_{i}"))).collect();
+    m.objective.extend(&x);
+    m.add(x.iter().map(|&v| (1, v)).collect(), Sense::Ge, 1);
+    for (r, rk) in rounds[..rounds.len() - 1].iter().enumerate() {
+        let y = if layers.mds {
+            let y: Vec<usize> = (0..CELLS).map(|i| m.var(format!("y_{r}_{i}"))).collect();
+            for c in 0..COLS {
+                let d = m.var(format!("d_{r}_{c}"));
+                let col: Vec<usize> = (0..ROWS)
+                    .flat_map(|row| [x[row * COLS + c], y[row * COLS + c]])
+                    .collect();
+                for &v in &col {
+                    m.add(vec![(1, d), (-1, v)], Sense::Ge, 0);
+                }
+                let mut terms: Vec<(i32, usize)> = col.iter().map(|&v| (1, v)).collect();
+                terms.push((-(branch as i32), d));
+                m.add(terms, Sense::Ge, 0);
+                for side in [&x, &y] {
+                    let mut terms: Vec<(i32, usize)> =
+                        (0..ROWS).map(|row| (1, side[row * COLS + c])).collect();
+                    terms.push((-1, d));
+                    m.add(terms, Sense::Ge, 0);
+                }
+            }
+            y
+        } else {
+            x.clone()
+        };
+        let next: Vec<usize> = (0..CELLS)
+            .map(|i| m.var(format!("x_{}_{i}", r + 1)))
+            .collect();
+        let mut inflow = vec![Vec::new(); CELLS];
+        for i in 0..CELLS {
+            let bits: Vec<usize> = (0..9).map(|k| m.var(format!("b_{r}_{i}_{k}"))).collect();
+            for (k, &b) in bits.iter().enumerate() {
+                m.add(vec![(1, b), (-1, y[i])], Sense::Le, 0);
+                inflow[bit_destination(i, k, rk, layers).0].push(b);
+            }
+            // 活跃 cell 至少有一位非零
+            let mut terms: Vec<(i32, usize)> = bits.iter().map(|&b| (1, b)).collect();
+            terms.push((-1, y[i]));
+            m.add(terms, Sense::Ge, 0);
+        }
+        for (j, bits) in inflow.iter().enumerate() {
+            for &b in bits {
+                m.add(vec![(1, next[j]), (-1, b)], Sense::Ge, 0);
+            }
+            let mut terms: Vec<(i32, usize)> = bits.iter().map(|&b| (1, b)).collect();
+            terms.push((-1, next[j]));
+            m.add(terms, Sense::Ge, 0);
+        }
+        m.objective.extend(&next);
+        x = next;
+    }
+    m
+}
+
+// MDS 输出 cell i 的第 k 位经 BitRotate、StreamFwd、VtxShuffle 后所在的 (cell, 位)；
+// 这几层都是比特置换，线性掩码的比特也按同样方式移动
+pub(crate) fn bit_destination(i: usize, k: usize, rk: &RoundKey, layers: Layers) -> (usize, usize) {
+    let (mut r, mut c, mut k) = (i / COLS, i % COLS, k);
+    if layers.bit_rotate {
+        k = (k + 1) % 9;
+    }
+    if layers.stream_fwd {
+        let (dr, dc) = VEC[rk.perm[k]];
+        r = (r as i8 + dr).rem_euclid(ROWS as i8) as usize;
+        c = (c as i8 + dc).rem_euclid(COLS as i8) as usize;
+    }
+    if layers.vtx_shuffle {
+        let s = rk.shift as usize;
+        r = (r + s) % ROWS;
+        c = (c + s) % COLS;
+    }
+    (r * COLS + c, k)
+}
+
+// --- MDS 分支数 ---
+fn mds_matrix() -> [[u16; ROWS]; ROWS] {
+    let mut m = [[0u16; ROWS]; ROWS];
+    for (r, row) in m.iter_mut().enumerate() {
+        for (k, v) in row.iter_mut().enumerate() {
+            *v = MDS_ROW[(k + ROWS - r) % ROWS];
+        }
+    }
+    m
+}
+// GF(2^9) 求逆：a^(2^9 - 2)
+fn gf_inv(a: u16) -> u16 {
+    let mut r = 1;
+    for _ in 0..510 {
+        r = gf_mul(r, a);
+    }
+    r
+}
+// 子矩阵 m[rows][cols] 在 GF(2^9) 上的秩
+fn rank(m: &[[u16; ROWS]; ROWS], rows: &[usize], cols: &[usize]) -> usize {
+    let mut a: Vec<Vec<u16>> = rows
+        .iter()
+        .map(|&r| cols.iter().map(|&c| m[r][c]).collect())
+        .collect();
+    let mut rank = 0;
+    for col in 0..cols.len() {
+        let Some(p) = (rank..a.len()).find(|&i| a[i][col] != 0) else {
+            continue;
+        };
+        a.swap(rank, p);
+        let inv = gf_inv(a[rank][col]);
+        for i in 0..a.len() {
+            if i != rank && a[i][col] != 0 {
+                let f = gf_mul(a[i][col], inv);
+                let pivot = a[rank].clone();
+                for (v, p) in a[i].iter_mut().zip(pivot) {
+                    *v ^= gf_mul(f, p);
+                }
+            }
+        }
+        rank += 1;
+    }
+    rank
+}
+// 分支数 = min(|I| + ROWS - |Z|)，取遍使子矩阵 m[Z][I] 的秩小于 |I| 的 (I, Z)：
+// 此时存在支撑在 I 内的非零输入，其输出在 Z 上全为零
+fn branch_number(m: &[[u16; ROWS]; ROWS]) -> usize {
+    let subset = |mask: usize| {
+        (0..ROWS)
+            .filter(|&i| mask >> i & 1 != 0)
+            .collect::<Vec<_>>()
+    };
+    let mut best = 2 * ROWS;
+    for in_mask in 1..1 << ROWS {
+        for zero_mask in 0..1 << ROWS {
+            let (inputs, zeros) = (subset(in_mask), subset(zero_mask));
+            if rank(m, &zeros, &inputs) < inputs.len() {
+                best = best.min(inputs.len() + ROWS - zeros.len());
+            }
+        }
+    }
+    best
+}
+/// 列 MDS 的差分分支数：任一非零输入列与其输出列的非零 cell 数之和的最小值。
+pub fn mds_branch_number() -> usize {
+    branch_number(&mds_matrix())
+}
+/// 列 MDS 的线性分支数：输入输出掩码的非零 cell 数之和的最小值，等于转置矩阵的差分分支数。
+pub fn mds_linear_branch_number() -> usize {
+    let m = mds_matrix();
+    let mut t = [[0u16; ROWS]; ROWS];
+    for (r, row) in t.iter_mut().enumerate() {
+        for (k, v) in row.iter_mut().enumerate() {
+            *v = m[k][r];
+        }
+    }
+    branch_number(&t)
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use common::{ctx, sample};
+use std::io::{Cursor, Read, Write};
+use wave_vortex::{
+    CipherCtx, DecryptReader, EncryptWriter, StreamMode, decrypt_stream_with_ctx,
+    encrypt_stream_with_ctx, open_segmented_with_ctx, open_stream_with_ctx,
+    seal_segmented_with_ctx, seal_stream_with_ctx,
+};
+
+const MODES: [StreamMode; 3] = [StreamMode::Cbc, StreamMode::Eax, StreamMode::Segmented];
+// 覆盖空输入、块边界 (32 / 36 字节) 和 EAX 的批处理边界
+const LENGTHS: [usize; 8] = [0, 1, 31, 32, 33, 36, 1000, 36 * 1024 + 5];
+
+fn one_shot_seal(mode: StreamMode, ctx: &CipherCtx, data: &[u8]) -> Vec<u8> {
+    let mut sealed = Vec::new();
+    let reader = &mut Cursor::new(data);
+    match mode {
+        StreamMode::Cbc => encrypt_stream_with_ctx(reader, &mut sealed, ctx),
+        StreamMode::Eax => seal_stream_with_ctx(reader, &mut sealed, ctx, b""),
+        StreamMode::Segmented => seal_segmented_with_ctx(reader, &mut sealed, ctx, b"", 1),
+    }
+    .unwrap();
+    sealed
+}
+fn one_shot_open(mode: StreamMode, ctx: &CipherCtx, sealed: &[u8]) -> Vec<u8> {
+    let mut opened = Vec::new();
+    let reader = &mut Cursor::new(sealed);
+    match mode {
+        StreamMode::Cbc => decrypt_stream_with_ctx(reader, &mut opened, ctx),
+        StreamMode::Eax => open_stream_with_ctx(reader, &mut opened, ctx, b""),
+        StreamMode::Segmented => open_segmented_with_ctx(reader, &mut opened, ctx, b"", 1),
+    }
+    .unwrap();
+    opened
+}
+
+// 适配器的输出能被一次性函数解密，反之亦然；写入按不规则的小块进行
+#[test]
+fn adapters_interoperate_with_one_shot_functions() {
+    let ctx = ctx();
+    for mode in MODES {
+        for len in LENGTHS {
+            let data = sample(len);
+            let mut writer = EncryptWriter::new(Vec::new(), ctx.clone(), mode);
+            for chunk in data.chunks(37) {
+                writer.write_all(chunk).unwrap();
+            }
+            let sealed = writer.finish().unwrap();
+            assert_eq!(one_shot_open(mode, &ctx, &sealed), data, "{mode:?} {len}");
+
+            let sealed = one_shot_seal(mode, &ctx, &data);
+            let mut reader = DecryptReader::new(Cursor::new(sealed), ctx.clone(), mode);
+            let mut opened = Vec::new();
+            let mut buf = [0u8; 29];
+            loop {
+                let n = reader.read(&mut buf).unwrap();
+                if n == 0 {
+                    break;
+                }
+                opened.extend_from_slice(&buf[..n]);
+            }
+            assert_eq!(opened, data, "{mode:?} {len}");
+        }
+    }
+}
+
+// 与其他 Write/Read 层叠加：加密写入器套在 BufWriter 外，解密读取器包着 Take
+#[test]
+fn adapters_layer_into_pipelines() {
+    let ctx = ctx();
+    let data = sample(5000);
+    for mode in MODES {
+        let mut writer = EncryptWriter::with_ad(
+            std::io::BufWriter::new(Vec::new()),
+            ctx.clone(),
+            mode,
+            b"header",
+        );
+        writer.write_all(&data).unwrap();
+        writer.flush().unwrap();
+        let sealed = writer.finish().unwrap().into_inner().unwrap();
+
+        let mut framed = sealed.clone();
+        framed.extend_from_slice(b"trailer");
+        let limited = Cursor::new(framed).take(sealed.len() as u64);
+        let mut reader = DecryptReader::with_ad(limited, ctx.clone(), mode, b"header");
+        let mut opened = Vec::new();
+        reader.read_to_end(&mut opened).unwrap();
+        assert_eq!(opened, data);
+        let mut rest = Vec::new();
+        reader
+            .into_inner()
+            .into_inner()
+            .read_to_end(&mut rest)
+            .unwrap();
+        assert_eq!(rest, b"trailer");
+    }
+}
+
+#[test]
+fn readers_reject_truncated_or_modified_input() {
+    let ctx = ctx();
+    let data = sample(1000);
+    // CBC 无认证，但不完整的末块必须报错，而不是当作正常结束
+    for mode in MODES {
+        let sealed = one_shot_seal(mode, &ctx, &data);
+        let truncated = &sealed[..sealed.len() - 1];
+        let mut reader = DecryptReader::new(Cursor::new(truncated), ctx.clone(), mode);
+        assert!(reader.read_to_end(&mut Vec::new()).is_err(), "{mode:?}");
+        assert!(reader.read(&mut [0u8; 8]).is_err(), "{mode:?}");
+    }
+    for mode in [StreamMode::Eax, StreamMode::Segmented] {
+        let mut sealed = one_shot_seal(mode, &ctx, &data);
+        sealed[40] ^= 1;
+        let mut reader = DecryptReader::new(Cursor::new(&sealed), ctx.clone(), mode);
+        assert!(reader.read_to_end(&mut Vec::new()).is_err(), "{mode:?}");
+
+        let mut writer = EncryptWriter::with_ad(Vec::new(), ctx.clone(), mode, b"a");
+        writer.write_all(&data).unwrap();
+        let sealed = writer.finish().unwrap();
+        let mut reader = DecryptReader::with_ad(Cursor::new(sealed), ctx.clone(), mode, b"b");
+        assert!(reader.read_to_end(&mut Vec::new()).is_err(), "{mode:?}");
+    }
+}
@@ -0,0 +1,115 @@
+mod common;
+
+use common::{ctx, sample};
+use wave_vortex::{
+    AuthError, CipherCtx, NONCE_SIZE, TAG_SIZE, open, open_in_place_detached, seal,
+    seal_in_place_detached,
+};
+
+const NONCE: [u8; NONCE_SIZE] = [9; NONCE_SIZE];
+
+#[test]
+fn roundtrip_and_length() {
+    let ctx = ctx();
+    for len in [0, 1, 35, 36, 37, 1000] {
+        let data = sample(len);
+        let sealed = seal(&ctx, &NONCE, b"header", &data);
+        assert_eq!(sealed.len(), len + TAG_SIZE);
+        assert_eq!(open(&ctx, &NONCE, b"header", &sealed).unwrap(), data);
+    }
+}
+
+// 标签、密文、关联数据、nonce 任一处改动都必须被拒绝
+#[test]
+fn any_modification_is_rejected() {
+    let ctx = ctx();
+    let data = sample(100);
+    let sealed = seal(&ctx, &NONCE, b"header", &data);
+    for i in [0, 50, 99, 100, 100 + TAG_SIZE - 1] {
+        let mut bad = sealed.clone();
+        bad[i] ^= 0x01;
+        assert_eq!(
+            open(&ctx, &NONCE, b"header", &bad),
+            Err(AuthError),
+            "byte {i}"
+        );
+    }
+    assert_eq!(open(&ctx, &NONCE, b"headex", &sealed), Err(AuthError));
+    assert_eq!(open(&ctx, &NONCE, b"", &sealed), Err(AuthError));
+    let mut nonce = NONCE;
+    nonce[NONCE_SIZE - 1] ^= 0x80;
+    assert_eq!(open(&ctx, &nonce, b"header", &sealed), Err(AuthError));
+    let other = CipherCtx::new(&[0x43; 32]);
+    assert_eq!(open(&other, &NONCE, b"header", &sealed), Err(AuthError));
+}
+
+// 标签取 OMAC 输出的前 TAG_SIZE 字节：截短、补长都不能通过
+#[test]
+fn truncated_or_extended_input_is_rejected() {
+    let ctx = ctx();
+    let sealed = seal(&ctx, &NONCE, b"", &sample(40));
+    for len in [0, 1, TAG_SIZE - 1, TAG_SIZE, sealed.len() - 1] {
+        assert_eq!(
+            open(&ctx, &NONCE, b"", &sealed[..len]),
+            Err(AuthError),
+            "{len}"
+        );
+    }
+    let mut extended = sealed.clone();
+    extended.push(0);
+    assert_eq!(open(&ctx, &NONCE, b"", &extended), Err(AuthError));
+}
+
+// 空消息只有标签，仍然认证 nonce 和关联数据
+#[test]
+fn empty_message() {
+    let ctx = ctx();
+    let sealed = seal(&ctx, &NONCE, b"", &[]);
+    assert_eq!(sealed.len(), TAG_SIZE);
+    assert_eq!(open(&ctx, &NONCE, b"", &sealed).unwrap(), Vec::<u8>::new());
+    let mut bad = sealed.clone();
+    bad[0] ^= 1;
+    assert_eq!(open(&ctx, &NONCE, b"", &bad), Err(AuthError));
+    assert_ne!(sealed, seal(&ctx, &[0; NONCE_SIZE], b"", &[]));
+}
+
+// 只有关联数据：相当于 MAC，改动关联数据必须被发现
+#[test]
+fn associated_data_only() {
+    let ctx = ctx();
+    let ad = sample(1000);
+    let mut buffer = [];
+    let tag = seal_in_place_detached(&ctx, &NONCE, &ad, &mut buffer);
+    assert!(open_in_place_detached(&ctx, &NONCE, &ad, &mut buffer, &tag).is_ok());
+    let mut bad_ad = ad.clone();
+    bad_ad[999] ^= 0x40;
+    assert_eq!(
+        open_in_place_detached(&ctx, &NONCE, &bad_ad, &mut buffer, &tag),
+        Err(AuthError)
+    );
+    assert_eq!(
+        open_in_place_detached(&ctx, &NONCE, &ad[..999], &mut buffer, &tag),
+        Err(AuthError)
+    );
+    // 空消息与空关联数据的标签不同
+    assert_ne!(tag, seal_in_place_detached(&ctx, &NONCE, b"", &mut []));
+}
+
+// 校验失败时缓冲区保持为密文
+#[test]
+fn failed_open_leaves_buffer_untouched() {
+    let ctx = ctx();
+    let data = sample(77);
+    let mut buffer = data.clone();
+    let mut tag = seal_in_place_detached(&ctx, &NONCE, b"ad", &mut buffer);
+    let ciphertext = buffer.clone();
+    tag[TAG_SIZE - 1] ^= 1;
+    assert_eq!(
+        open_in_place_detached(&ctx, &NONCE, b"ad", &mut buffer, &tag),
+        Err(AuthError)
+    );
+    assert_eq!(buffer, ciphertext);
+    tag[TAG_SIZE - 1] ^= 1;
+    open_in_place_detached(&ctx, &NONCE, b"ad", &mut buffer, &tag).unwrap();
+    assert_eq!(buffer, data);
+}
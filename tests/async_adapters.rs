@@ -0,0 +1,98 @@
+#![cfg(feature = "async")]
+
+mod common;
+
+use common::{ctx, sample};
+use std::io::{Cursor, Read, Write};
+use tokio::io::{AsyncReadExt, AsyncWriteExt, duplex};
+use wave_vortex::{
+    AsyncDecryptReader, AsyncEncryptWriter, DecryptReader, EncryptWriter, StreamMode,
+};
+
+const MODES: [StreamMode; 3] = [StreamMode::Cbc, StreamMode::Eax, StreamMode::Segmented];
+
+// 通过很小的 duplex 缓冲区加密，迫使写入器反复遇到背压
+async fn seal_over_duplex(mode: StreamMode, data: Vec<u8>) -> Vec<u8> {
+    let (client, mut server) = duplex(64);
+    let writer = tokio::spawn(async move {
+        let mut writer = AsyncEncryptWriter::with_ad(client, ctx(), mode, b"ad");
+        for chunk in data.chunks(1000) {
+            writer.write_all(chunk).await.unwrap();
+        }
+        writer.shutdown().await.unwrap();
+    });
+    let mut sealed = Vec::new();
+    server.read_to_end(&mut sealed).await.unwrap();
+    writer.await.unwrap();
+    sealed
+}
+async fn open_over_duplex(mode: StreamMode, sealed: Vec<u8>) -> std::io::Result<Vec<u8>> {
+    let (mut client, server) = duplex(64);
+    let writer = tokio::spawn(async move {
+        // 读取端出错提前退出时，写入会失败，这里不关心
+        let _ = client.write_all(&sealed).await;
+        let _ = client.shutdown().await;
+    });
+    let mut reader = AsyncDecryptReader::with_ad(server, ctx(), mode, b"ad");
+    let mut opened = Vec::new();
+    let result = reader.read_to_end(&mut opened).await;
+    drop(reader);
+    writer.await.unwrap();
+    result.map(|_| opened)
+}
+
+#[tokio::test]
+async fn async_output_is_readable_by_sync_reader() {
+    for mode in MODES {
+        for len in [0, 31, 32, 5000, 70_000] {
+            let data = sample(len);
+            let sealed = seal_over_duplex(mode, data.clone()).await;
+            let mut reader = DecryptReader::with_ad(Cursor::new(sealed), ctx(), mode, b"ad");
+            let mut opened = Vec::new();
+            reader.read_to_end(&mut opened).unwrap();
+            assert_eq!(opened, data, "{mode:?} {len}");
+        }
+    }
+}
+
+#[tokio::test]
+async fn sync_output_is_readable_by_async_reader() {
+    for mode in MODES {
+        for len in [0, 31, 32, 5000, 70_000] {
+            let data = sample(len);
+            let mut writer = EncryptWriter::with_ad(Vec::new(), ctx(), mode, b"ad");
+            writer.write_all(&data).unwrap();
+            let sealed = writer.finish().unwrap();
+            let opened = open_over_duplex(mode, sealed).await.unwrap();
+            assert_eq!(opened, data, "{mode:?} {len}");
+        }
+    }
+}
+
+#[tokio::test]
+async fn async_reader_rejects_truncated_and_modified_input() {
+    for mode in MODES {
+        let sealed = seal_over_duplex(mode, sample(2000)).await;
+        let truncated = sealed[..sealed.len() - 1].to_vec();
+        assert!(open_over_duplex(mode, truncated).await.is_err(), "{mode:?}");
+    }
+    for mode in [StreamMode::Eax, StreamMode::Segmented] {
+        let mut sealed = seal_over_duplex(mode, sample(2000)).await;
+        sealed[100] ^= 1;
+        assert!(open_over_duplex(mode, sealed).await.is_err(), "{mode:?}");
+    }
+}
+
+// finish() 只刷新不关闭内部写入器，并把它交还给调用者
+#[tokio::test]
+async fn finish_returns_inner_writer() {
+    let data = sample(100);
+    let mut writer = AsyncEncryptWriter::new(Vec::new(), ctx(), StreamMode::Segmented);
+    writer.write_all(&data).await.unwrap();
+    let sealed = writer.finish().await.unwrap();
+
+    let mut reader = AsyncDecryptReader::new(&sealed[..], ctx(), StreamMode::Segmented);
+    let mut opened = Vec::new();
+    reader.read_to_end(&mut opened).await.unwrap();
+    assert_eq!(opened, data);
+}
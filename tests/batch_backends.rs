@@ -0,0 +1,45 @@
+use rand::{RngCore, SeedableRng, rngs::StdRng};
+use wave_vortex::{BatchBackend, CipherCtx, encrypt_block_288};
+
+const BACKENDS: [BatchBackend; 4] = [
+    BatchBackend::Portable,
+    BatchBackend::Sse2,
+    BatchBackend::Avx2,
+    BatchBackend::Neon,
+];
+
+#[test]
+fn detected_backend_is_available() {
+    assert!(BatchBackend::detect().is_available());
+    assert!(BatchBackend::Portable.is_available());
+}
+
+// 覆盖不满一组、跨组和跨批的长度
+#[test]
+fn available_backends_match_single_block_path() {
+    let mut rng = StdRng::seed_from_u64(0x5349_4d44);
+    let mut key = [0u8; 32];
+    rng.fill_bytes(&mut key);
+    let ctx = CipherCtx::new(&key);
+    for backend in BACKENDS.into_iter().filter(|b| b.is_available()) {
+        for n in [1, 63, 64, 65, 200, 257] {
+            let mut blocks = vec![[0u8; 36]; n];
+            for block in blocks.iter_mut() {
+                rng.fill_bytes(block);
+            }
+            let plain = blocks.clone();
+            backend.encrypt_blocks(&ctx, &mut blocks);
+            for (pt, ct) in plain.iter().zip(&blocks) {
+                assert_eq!(
+                    encrypt_block_288(&ctx, pt),
+                    *ct,
+                    "{:?}, {} blocks",
+                    backend,
+                    n
+                );
+            }
+            backend.decrypt_blocks(&ctx, &mut blocks);
+            assert_eq!(blocks, plain, "{:?}, {} blocks", backend, n);
+        }
+    }
+}
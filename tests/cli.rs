@@ -0,0 +1,36 @@
+#![cfg(unix)]
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("wvx-cli-{}-{name}", std::process::id()))
+}
+fn keygen(path: &PathBuf, force: bool) {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_wave_vortex"));
+    cmd.arg("keygen").arg("-o").arg(path);
+    if force {
+        cmd.arg("--force");
+    }
+    assert!(cmd.status().unwrap().success());
+}
+fn mode(path: &PathBuf) -> u32 {
+    fs::metadata(path).unwrap().permissions().mode() & 0o777
+}
+
+// 密钥文件只允许属主读写，--force 覆盖宽松权限的旧文件时也一样
+#[test]
+fn keygen_creates_private_key_file() {
+    let path = temp_path("key");
+    let _ = fs::remove_file(&path);
+    keygen(&path, false);
+    assert_eq!(fs::read(&path).unwrap().len(), 32);
+    assert_eq!(mode(&path), 0o600);
+
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+    keygen(&path, true);
+    assert_eq!(mode(&path), 0o600);
+    fs::remove_file(&path).unwrap();
+}
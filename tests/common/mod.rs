@@ -0,0 +1,36 @@
+// 各集成测试共用的密钥、样本数据和状态打包；每个测试 crate 只用到其中一部分
+#![allow(dead_code)]
+
+use wave_vortex::{CipherCtx, TweakableCtx};
+
+pub const KEY: [u8; 32] = [0x42; 32];
+
+pub fn ctx() -> CipherCtx {
+    CipherCtx::new(&KEY)
+}
+pub fn tweakable_ctx() -> TweakableCtx {
+    TweakableCtx::new(&KEY)
+}
+// 非周期的样本数据，避免相同块掩盖链接或计数器错误
+pub fn sample(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i * 7 + i / 13) as u8).collect()
+}
+// 与库中的状态打包方式一致：cell 依次排列，每个 cell 低位在前占 9 位
+pub fn pack(cells: &[u16; 32]) -> [u8; 36] {
+    let mut out = [0u8; 36];
+    for (i, &cell) in cells.iter().enumerate() {
+        for bit in 0..9 {
+            let pos = i * 9 + bit;
+            out[pos / 8] |= (((cell >> bit) & 1) as u8) << (pos % 8);
+        }
+    }
+    out
+}
+pub fn unpack(bytes: &[u8; 36]) -> [u16; 32] {
+    std::array::from_fn(|i| {
+        (0..9).fold(0, |acc, bit| {
+            let pos = i * 9 + bit;
+            acc | (((bytes[pos / 8] >> (pos % 8)) & 1) as u16) << bit
+        })
+    })
+}
@@ -0,0 +1,35 @@
+use rand::{RngCore, SeedableRng, rngs::StdRng};
+use wave_vortex::{
+    CipherCtx, ROUND_IMPL, RoundImpl, decrypt_block_288, encrypt_block_288, encrypt_blocks_ctx,
+};
+
+#[test]
+fn compiled_round_impl_matches_feature() {
+    let expected = if cfg!(feature = "constant_time") {
+        RoundImpl::ConstantTime
+    } else {
+        RoundImpl::Tables
+    };
+    assert_eq!(ROUND_IMPL, expected);
+}
+
+// 位切片引擎是独立实现：单块路径 (查表或常数时间) 必须与它逐位一致
+#[test]
+fn single_block_path_matches_bitsliced_engine() {
+    let mut rng = StdRng::seed_from_u64(0x5756_5846);
+    for _ in 0..4 {
+        let mut key = [0u8; 32];
+        rng.fill_bytes(&mut key);
+        let ctx = CipherCtx::new(&key);
+        let mut blocks = vec![[0u8; 36]; 70];
+        for block in blocks.iter_mut() {
+            rng.fill_bytes(block);
+        }
+        let plain = blocks.clone();
+        encrypt_blocks_ctx(&ctx, &mut blocks);
+        for (pt, ct) in plain.iter().zip(&blocks) {
+            assert_eq!(encrypt_block_288(&ctx, pt), *ct);
+            assert_eq!(decrypt_block_288(&ctx, ct), *pt);
+        }
+    }
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use common::{ctx, sample};
+use std::io::ErrorKind;
+use wave_vortex::{CTR_NONCE_SIZE, CipherCtx, Ctr, encrypt_block_288};
+
+const NONCE: [u8; CTR_NONCE_SIZE] = [0xc7; CTR_NONCE_SIZE];
+// 超过 16 个整块会走批量引擎，末尾再留一个残块
+const LEN: usize = 36 * 70 + 5;
+
+// 参考实现：第 i 块为 E(i_le64 || nonce)
+fn reference_keystream(ctx: &CipherCtx, len: usize) -> Vec<u8> {
+    let mut out = Vec::new();
+    for i in 0..len.div_ceil(36) as u64 {
+        let mut block = [0u8; 36];
+        block[..8].copy_from_slice(&i.to_le_bytes());
+        block[8..].copy_from_slice(&NONCE);
+        out.extend_from_slice(&encrypt_block_288(ctx, &block));
+    }
+    out.truncate(len);
+    out
+}
+
+#[test]
+fn keystream_matches_counter_blocks() {
+    let ctx = ctx();
+    let mut data = vec![0u8; LEN];
+    let mut ctr = Ctr::new(&ctx, &NONCE);
+    ctr.apply_keystream(&mut data);
+    assert_eq!(data, reference_keystream(&ctx, LEN));
+    assert_eq!(ctr.position(), LEN as u64);
+}
+
+// 批量路径与逐字节路径给出同样的结果，且加解密互逆
+#[test]
+fn bulk_matches_byte_by_byte() {
+    let ctx = ctx();
+    let data = sample(LEN);
+    let mut bulk = data.clone();
+    Ctr::new(&ctx, &NONCE).apply_keystream(&mut bulk);
+    let mut bytewise = data.clone();
+    let mut ctr = Ctr::new(&ctx, &NONCE);
+    for b in bytewise.chunks_mut(1) {
+        ctr.apply_keystream(b);
+    }
+    assert_eq!(bulk, bytewise);
+    Ctr::new(&ctx, &NONCE).apply_keystream(&mut bulk);
+    assert_eq!(bulk, data);
+}
+
+#[test]
+fn seek_into_middle_of_block() {
+    let ctx = ctx();
+    let data = sample(LEN);
+    let mut whole = data.clone();
+    Ctr::new(&ctx, &NONCE).apply_keystream(&mut whole);
+    for pos in [1, 35, 37, 36 * 3 + 17, 36 * 20 + 1, LEN - 1] {
+        let mut tail = data[pos..].to_vec();
+        let mut ctr = Ctr::new(&ctx, &NONCE);
+        // 先读一段再回跳，确保缓存的密钥流块不会被误用
+        ctr.apply_keystream(&mut [0u8; 50]);
+        ctr.seek(pos as u64);
+        assert_eq!(ctr.position(), pos as u64);
+        ctr.apply_keystream(&mut tail);
+        assert_eq!(tail, whole[pos..], "pos {pos}");
+    }
+}
+
+// 位置溢出 u64 时返回错误，数据和位置保持不变
+#[test]
+fn position_overflow_is_an_error() {
+    let ctx = ctx();
+    let mut ctr = Ctr::new(&ctx, &NONCE);
+    ctr.seek(u64::MAX - 3);
+    let mut data = [0u8; 4];
+    let err = ctr.try_apply_keystream(&mut data).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert_eq!(data, [0u8; 4]);
+    assert_eq!(ctr.position(), u64::MAX - 3);
+    ctr.try_apply_keystream(&mut data[..3]).unwrap();
+    assert_eq!(ctr.position(), u64::MAX);
+    assert_ne!(data[..3], [0u8; 3]);
+}
+
+#[test]
+#[should_panic(expected = "overflowed")]
+fn apply_keystream_panics_on_overflow() {
+    let ctx = ctx();
+    let mut ctr = Ctr::new(&ctx, &NONCE);
+    ctr.seek(u64::MAX);
+    ctr.apply_keystream(&mut [0u8; 1]);
+}
@@ -0,0 +1,152 @@
+mod common;
+
+use common::sample;
+use std::io::{Cursor, ErrorKind};
+use std::time::{Duration, Instant};
+use wave_vortex::{
+    FileHeader, Kdf, MAX_ARGON2_P_COST, MAX_ARGON2_T_COST, MAX_PBKDF2_ITERATIONS, MAX_SCRYPT_P,
+    StreamMode, decrypt_stream, encrypt_stream_with_header,
+};
+
+const PASSWORD: &[u8] = b"password";
+
+// 引入文件头之前的 encrypt_stream 的输出：salt(16) || iv(32) || CBC 密文，
+// 密钥为 PBKDF2-HMAC-SHA256(password, salt, 100000)，明文为 sample(len)
+const LEGACY_FILES: [(usize, &str); 2] = [
+    (
+        0,
+        "a16941340f3c30cdcbfc27c93bef9c9ac44310ccd0e329ad12075f1b843be8070c3a041f12da762c\
+         cde72e7925cb9406f0eaad92d12ca05a2b5f16242402485bc8de8b066c4bdf052c82a5a9a10aa03f\
+         78797370",
+    ),
+    (
+        70,
+        "421b116405398e4100146f2355cd523f55db16e2d524618f39d83d8ea36cbfe9966e185ec9463f39\
+         7c8719c9e8466ab3a35f3befde4688a9b7468013d1cbdd65413b6a06ad3f934cf324ec912473fa42\
+         d4d5b63ba3146ad904100f49785de1fc3359c649f43074f1fd66bf0a445363e0124d2900c5416c68\
+         0edc51a53acb963b6af0201ea2fc8d6ecbda03555b275fb584dc6790ab9d026c73134661",
+    ),
+];
+
+fn unhex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+fn decrypt(file: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    decrypt_stream(&mut Cursor::new(file), &mut out, PASSWORD).map(|()| out)
+}
+fn header() -> FileHeader {
+    FileHeader {
+        kdf: Kdf::Pbkdf2Sha256 { iterations: 1000 },
+        salt: vec![3; 16],
+        mode: StreamMode::Cbc,
+        nonce: vec![5; StreamMode::Cbc.nonce_size()],
+    }
+}
+fn seal(header: &FileHeader, data: &[u8]) -> Vec<u8> {
+    let mut file = Vec::new();
+    encrypt_stream_with_header(&mut Cursor::new(data), &mut file, PASSWORD, header, 1).unwrap();
+    file
+}
+
+#[test]
+fn legacy_headerless_files_still_decrypt() {
+    for (len, file) in LEGACY_FILES {
+        let file = unhex(file);
+        assert_eq!(decrypt(&file).unwrap(), sample(len), "{len} bytes");
+        // 旧格式没有认证，只能靠填充发现错误的口令
+        let mut out = Vec::new();
+        assert!(decrypt_stream(&mut Cursor::new(&file), &mut out, b"wrong").is_err());
+    }
+}
+
+// 文件头在任意位置截断都返回错误
+#[test]
+fn truncated_header_is_an_error() {
+    let header = header();
+    let header_len = header.to_bytes().len();
+    let file = seal(&header, &sample(40));
+    for len in 0..header_len {
+        let err = decrypt(&file[..len]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof, "{len} bytes");
+    }
+}
+
+// 改坏版本、KDF、长度字段和模式都返回 InvalidData，而不是 panic 或按错误的布局继续解析
+#[test]
+fn garbled_header_is_an_error() {
+    let header = header();
+    let file = seal(&header, &sample(40));
+    // magic(4) version kdf_id params_len params(4) salt_len salt(16) mode_id nonce_len nonce(32)
+    let cases: [(usize, u8); 9] = [
+        (4, 0),
+        (4, 2),
+        (5, 0),
+        (5, 9),
+        (6, 3),
+        (11, 0),
+        (28, 0),
+        (28, 7),
+        (29, 24),
+    ];
+    for (pos, value) in cases {
+        let mut bad = file.clone();
+        bad[pos] = value;
+        let err = decrypt(&bad).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData, "byte {pos} = {value}");
+    }
+    assert!(FileHeader::read_from(&mut Cursor::new(b"WVXG\x01")).is_err());
+    assert_eq!(
+        FileHeader::read_from(&mut Cursor::new(&file)).unwrap(),
+        header
+    );
+}
+
+// 文件头中的 KDF 参数未经认证：超出上限的计算量要在派生密钥之前就被拒绝
+#[test]
+fn excessive_kdf_work_is_refused_before_derivation() {
+    let excessive = [
+        Kdf::Pbkdf2Sha256 {
+            iterations: u32::MAX,
+        },
+        Kdf::Pbkdf2Sha256 {
+            iterations: MAX_PBKDF2_ITERATIONS + 1,
+        },
+        Kdf::Argon2id {
+            m_cost: 64,
+            t_cost: MAX_ARGON2_T_COST + 1,
+            p_cost: 1,
+        },
+        Kdf::Argon2id {
+            m_cost: 64 * 1024,
+            t_cost: 1,
+            p_cost: MAX_ARGON2_P_COST + 1,
+        },
+        Kdf::Scrypt {
+            log_n: 4,
+            r: 8,
+            p: MAX_SCRYPT_P + 1,
+        },
+    ];
+    for kdf in excessive {
+        let header = FileHeader { kdf, ..header() };
+        let mut file = header.to_bytes();
+        file.extend_from_slice(&[0u8; 72]);
+        let start = Instant::now();
+        let err = decrypt(&file).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData, "{kdf:?}");
+        assert!(start.elapsed() < Duration::from_secs(1), "{kdf:?}");
+        assert!(FileHeader::read_from(&mut Cursor::new(&file)).is_err());
+        assert!(kdf.derive_key(PASSWORD, &[1; 16]).is_err());
+    }
+    // 上限本身仍然可用
+    let kdf = Kdf::Argon2id {
+        m_cost: 64,
+        t_cost: MAX_ARGON2_T_COST,
+        p_cost: 1,
+    };
+    assert!(kdf.derive_key(PASSWORD, &[1; 16]).is_ok());
+}
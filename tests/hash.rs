@@ -0,0 +1,76 @@
+mod common;
+
+use common::sample;
+use digest::Digest;
+use wave_vortex::{HASH_SIZE, Hasher, hash, keyed_hash};
+
+// 覆盖长度字段恰好放下 / 放不下、整块与多块的情况
+#[test]
+fn streaming_matches_one_shot_and_lengths_are_distinct() {
+    let mut seen = Vec::new();
+    for len in [0, 1, 27, 28, 29, 35, 36, 37, 72, 500] {
+        let data = sample(len);
+        let expected = hash(&data);
+        for chunk in [1, 7, 36, 100] {
+            let mut hasher = Hasher::new();
+            for part in data.chunks(chunk) {
+                hasher.update(part);
+            }
+            assert_eq!(hasher.finalize(), expected, "{len} {chunk}");
+        }
+        assert!(!seen.contains(&expected), "{len}");
+        seen.push(expected);
+    }
+    // 尾部补零不能与原消息碰撞 (MD 强化)
+    assert_ne!(hash(b"abc"), hash(b"abc\0"));
+    assert_ne!(hash(b""), hash(&[0x80]));
+}
+
+#[test]
+fn single_bit_changes_flip_about_half_the_output() {
+    let data = sample(100);
+    let base = hash(&data);
+    for bit in [0, 7, 400, 799] {
+        let mut changed = data.clone();
+        changed[bit / 8] ^= 1 << (bit % 8);
+        let out = hash(&changed);
+        let diff: u32 = out
+            .iter()
+            .zip(&base)
+            .map(|(a, b)| (a ^ b).count_ones())
+            .sum();
+        assert!((80..=176).contains(&diff), "bit {bit}: {diff}");
+    }
+}
+
+#[test]
+fn keyed_mode_depends_on_key() {
+    let data = sample(64);
+    let a = keyed_hash(&[1; 32], &data);
+    assert_ne!(a, keyed_hash(&[2; 32], &data));
+    assert_ne!(a, hash(&data));
+    // 无密钥模式等价于全零密钥
+    assert_eq!(keyed_hash(&[0; 32], &data), hash(&data));
+}
+
+#[test]
+fn digest_trait_interop() {
+    let data = sample(90);
+    let out = <Hasher as Digest>::digest(&data);
+    assert_eq!(out.len(), HASH_SIZE);
+    assert_eq!(out.as_slice(), hash(&data));
+
+    // 重置保留密钥
+    let mut keyed = Hasher::new_keyed(&[9; 32]);
+    Digest::update(&mut keyed, b"discarded");
+    Digest::reset(&mut keyed);
+    Digest::update(&mut keyed, &data);
+    assert_eq!(
+        keyed.finalize_reset().as_slice(),
+        keyed_hash(&[9; 32], &data)
+    );
+    assert_eq!(
+        Digest::finalize(keyed).as_slice(),
+        keyed_hash(&[9; 32], b"")
+    );
+}
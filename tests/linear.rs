@@ -0,0 +1,151 @@
+mod common;
+
+use common::{pack, unpack};
+use rand::{RngCore, SeedableRng, rngs::StdRng};
+use wave_vortex::{
+    CipherCtx, Layers, LinearSearch, LinearTrail, encrypt_block_288, encrypt_blocks_ctx,
+    mds_linear_branch_number, search_linear_trail,
+};
+
+fn ablations() -> [Layers; 5] {
+    [
+        Layers::ALL,
+        Layers {
+            mds: false,
+            ..Layers::ALL
+        },
+        Layers {
+            bit_rotate: false,
+            ..Layers::ALL
+        },
+        Layers {
+            stream_fwd: false,
+            ..Layers::ALL
+        },
+        Layers {
+            vtx_shuffle: false,
+            ..Layers::ALL
+        },
+    ]
+}
+fn parity(mask: &[u8; 36], x: &[u8; 36]) -> bool {
+    mask.iter()
+        .zip(x)
+        .map(|(m, b)| (m & b).count_ones())
+        .sum::<u32>()
+        & 1
+        != 0
+}
+fn search(ctx: &CipherCtx) -> LinearTrail {
+    search_linear_trail(ctx, &LinearSearch::default()).unwrap()
+}
+
+#[test]
+fn linear_branch_number_is_three() {
+    assert_eq!(mds_linear_branch_number(), 3);
+}
+
+// 一轮时输出掩码经线性层逆推回来只落在活跃 S 盒上，
+// 遍历该 cell 的全部取值即可精确算出相关度，应与特征给出的一致
+#[test]
+fn one_round_correlation_is_exact() {
+    let mut rng = StdRng::seed_from_u64(0x004c_494e);
+    for layers in ablations() {
+        for _ in 0..2 {
+            let mut key = [0u8; 32];
+            rng.fill_bytes(&mut key);
+            let ctx = CipherCtx::with_rounds(&key, 1).with_layers(layers);
+            let trail = search(&ctx);
+            assert!(trail.exhaustive_within_limits);
+            assert_eq!(trail.active_sboxes(), 1);
+            let active = trail.rounds[0].sboxes[0].cell;
+            let mut pt = [0u8; 36];
+            rng.fill_bytes(&mut pt);
+            let mut sum = 0i32;
+            for value in 0..512 {
+                let mut cells = unpack(&pt);
+                cells[active] = value;
+                pt = pack(&cells);
+                let ct = encrypt_block_288(&ctx, &pt);
+                let bit = parity(&trail.rounds[0].input, &pt) ^ parity(&trail.output, &ct);
+                sum += if bit { -1 } else { 1 };
+            }
+            assert_eq!(
+                (sum.abs() as f64) / 512.0,
+                trail.correlation(),
+                "{layers:?}"
+            );
+        }
+    }
+}
+
+// 两轮以上会有线性壳效应，只检查实测相关度明显不为零且量级与特征相符
+#[test]
+fn two_round_trail_is_observable() {
+    let ctx = CipherCtx::with_rounds(&[0x42u8; 32], 2);
+    let trail = search(&ctx);
+    let mut rng = StdRng::seed_from_u64(0x0032_524e);
+    let batches = 256;
+    let mut blocks = vec![[0u8; 36]; 4096];
+    let mut sum = 0i64;
+    for _ in 0..batches {
+        for block in blocks.iter_mut() {
+            rng.fill_bytes(block);
+        }
+        let plain = blocks.clone();
+        encrypt_blocks_ctx(&ctx, &mut blocks);
+        for (pt, ct) in plain.iter().zip(&blocks) {
+            let bit = parity(&trail.rounds[0].input, pt) ^ parity(&trail.output, ct);
+            sum += if bit { -1 } else { 1 };
+        }
+    }
+    let measured = sum.abs() as f64 / (batches * blocks.len()) as f64;
+    let expected = trail.correlation();
+    assert!(
+        measured > expected / 2.0 && measured < expected * 2.0,
+        "measured {measured}, trail {expected}"
+    );
+}
+
+#[test]
+fn trail_is_consistent() {
+    for n in 1..=4 {
+        let ctx = CipherCtx::with_rounds(&[7u8; 32], n);
+        let trail = search(&ctx);
+        assert_eq!(trail.rounds.len(), n);
+        let mut log2 = 0.0;
+        for round in &trail.rounds {
+            let input = unpack(&round.input);
+            let active: Vec<usize> = (0..32).filter(|&i| input[i] != 0).collect();
+            let cells: Vec<usize> = round.sboxes.iter().map(|s| s.cell).collect();
+            assert_eq!(cells, active);
+            for s in &round.sboxes {
+                assert_eq!(s.input, input[s.cell]);
+                assert!(s.output != 0 && s.correlation != 0.0);
+                assert!(s.correlation.abs() <= 44.0 / 512.0);
+                log2 += s.correlation.abs().log2();
+            }
+        }
+        assert!((trail.log2_correlation() - log2).abs() < 1e-9);
+        // 每轮至少一个活跃 S 盒，找到的特征不会超过这个上界
+        assert!(trail.log2_correlation() <= n as f64 * (44.0f64 / 512.0).log2() + 1e-9);
+        assert!(trail.nodes <= LinearSearch::default().max_nodes);
+    }
+}
+
+#[test]
+fn node_budget_is_respected() {
+    let ctx = CipherCtx::with_rounds(&[1u8; 32], 3);
+    let tiny = LinearSearch {
+        candidates: 2,
+        max_nodes: 1,
+    };
+    assert!(search_linear_trail(&ctx, &tiny).is_none());
+    let small = LinearSearch {
+        candidates: 2,
+        max_nodes: 500,
+    };
+    let trail = search_linear_trail(&ctx, &small).unwrap();
+    assert!(!trail.exhaustive_within_limits);
+    assert_eq!(trail.nodes, 500);
+}
@@ -0,0 +1,167 @@
+mod common;
+
+use common::{KEY, sample};
+use digest::Mac as _;
+use wave_vortex::{CipherCtx, MAC_SIZE, Mac, MacAlgorithm, encrypt_block_288};
+
+// 覆盖空消息、整块 / 残块末尾，以及跨多个 PMAC 批次 (64 块) 的长度
+const LENGTHS: [usize; 9] = [0, 1, 35, 36, 37, 72, 73, 36 * 64 + 1, 36 * 150];
+const ALGORITHMS: [MacAlgorithm; 2] = [MacAlgorithm::Cmac, MacAlgorithm::Pmac];
+
+// --- 逐块串行的参考实现，不经过批量引擎 ---
+// GF(2^288)，约化多项式 x^288 + x^11 + x^10 + x + 1，小端字节序
+fn dbl(b: &[u8; 36]) -> [u8; 36] {
+    let mut out = [0u8; 36];
+    for i in 0..36 {
+        out[i] = (b[i] << 1) | if i > 0 { b[i - 1] >> 7 } else { 0 };
+    }
+    if b[35] >> 7 == 1 {
+        out[0] ^= 0x03;
+        out[1] ^= 0x0C;
+    }
+    out
+}
+fn half(b: &[u8; 36]) -> [u8; 36] {
+    let mut v = *b;
+    let odd = v[0] & 1 == 1;
+    if odd {
+        v[0] ^= 0x03;
+        v[1] ^= 0x0C;
+    }
+    let mut out = [0u8; 36];
+    for i in 0..36 {
+        let next = if i < 35 { v[i + 1] } else { odd as u8 };
+        out[i] = (v[i] >> 1) | (next << 7);
+    }
+    out
+}
+fn xor(a: &mut [u8; 36], b: &[u8]) {
+    a.iter_mut().zip(b).for_each(|(x, y)| *x ^= y);
+}
+// 切出最后一块 (可能为空)，不满一块时做 10* 填充
+fn split_last(msg: &[u8]) -> (&[u8], [u8; 36], bool) {
+    let n = if msg.is_empty() {
+        0
+    } else {
+        (msg.len() - 1) / 36
+    };
+    let (head, tail) = msg.split_at(n * 36);
+    let mut last = [0u8; 36];
+    last[..tail.len()].copy_from_slice(tail);
+    if tail.len() < 36 {
+        last[tail.len()] = 0x80;
+    }
+    (head, last, tail.len() == 36)
+}
+fn reference(ctx: &CipherCtx, algorithm: MacAlgorithm, msg: &[u8]) -> [u8; 36] {
+    let l = encrypt_block_288(ctx, &[0; 36]);
+    let (head, mut last, full) = split_last(msg);
+    let mut state = [0u8; 36];
+    match algorithm {
+        MacAlgorithm::Cmac => {
+            for block in head.chunks(36) {
+                xor(&mut state, block);
+                state = encrypt_block_288(ctx, &state);
+            }
+            let k1 = dbl(&l);
+            xor(&mut last, &if full { k1 } else { dbl(&k1) });
+        }
+        MacAlgorithm::Pmac => {
+            let mut offset = [0u8; 36];
+            for (i, block) in head.chunks(36).enumerate() {
+                let mut li = l;
+                for _ in 0..(i + 1).trailing_zeros() {
+                    li = dbl(&li);
+                }
+                xor(&mut offset, &li);
+                let mut masked = offset;
+                xor(&mut masked, block);
+                xor(&mut state, &encrypt_block_288(ctx, &masked));
+            }
+            if full {
+                let l_inv = half(&l);
+                assert_eq!(dbl(&l_inv), l);
+                xor(&mut last, &l_inv);
+            }
+        }
+    }
+    xor(&mut state, &last);
+    encrypt_block_288(ctx, &state)
+}
+
+#[test]
+fn tags_match_serial_reference_for_any_chunking() {
+    let ctx = CipherCtx::new(&KEY);
+    for algorithm in ALGORITHMS {
+        for len in LENGTHS {
+            let msg = sample(len);
+            let expected = reference(&ctx, algorithm, &msg);
+            for chunk in [1, 35, 36, 100, 36 * 64 + 7] {
+                let mut mac = Mac::with_ctx(ctx.clone(), algorithm);
+                for part in msg.chunks(chunk) {
+                    mac.update(part);
+                }
+                assert_eq!(mac.finalize(), expected, "{algorithm:?} {len} {chunk}");
+            }
+        }
+    }
+}
+
+#[test]
+fn verify_rejects_modified_message_tag_or_key() {
+    for algorithm in ALGORITHMS {
+        let msg = sample(200);
+        let mut mac = Mac::with_ctx(CipherCtx::new(&KEY), algorithm);
+        mac.update(&msg);
+        let tag = mac.clone().finalize();
+        assert!(mac.verify(&tag).is_ok());
+
+        let check = |key: &[u8; 32], msg: &[u8], tag: &[u8; MAC_SIZE]| {
+            let mut mac = Mac::with_ctx(CipherCtx::new(key), algorithm);
+            mac.update(msg);
+            mac.verify(tag)
+        };
+        let mut bad_tag = tag;
+        bad_tag[MAC_SIZE - 1] ^= 1;
+        assert!(check(&KEY, &msg, &bad_tag).is_err());
+        let mut bad_msg = msg.clone();
+        bad_msg[3] ^= 0x40;
+        assert!(check(&KEY, &bad_msg, &tag).is_err());
+        assert!(check(&[0x4e; 32], &msg, &tag).is_err());
+        // 10* 填充后的残块与恰好等于填充结果的整块必须区分开
+        let mut padded = msg[..199].to_vec();
+        padded.push(0x80);
+        padded.resize(216, 0);
+        assert!(check(&KEY, &padded, &tag).is_err());
+    }
+    let msg = sample(100);
+    let mut cmac = Mac::cmac(&KEY);
+    let mut pmac = Mac::pmac(&KEY);
+    cmac.update(&msg);
+    pmac.update(&msg);
+    assert_ne!(cmac.finalize(), pmac.finalize());
+}
+
+#[test]
+fn digest_mac_trait_interop() {
+    let msg = sample(500);
+    let mut native = Mac::cmac(&KEY);
+    native.update(&msg);
+    let tag = native.finalize();
+
+    let mut mac = <Mac as digest::Mac>::new_from_slice(&KEY).unwrap();
+    assert_eq!(mac.algorithm(), MacAlgorithm::Cmac);
+    digest::Mac::update(&mut mac, &msg);
+    assert_eq!(mac.finalize_reset().into_bytes().as_slice(), tag);
+    digest::Mac::update(&mut mac, &msg);
+    assert!(mac.verify_slice(&tag).is_ok());
+    assert!(<Mac as digest::Mac>::new_from_slice(&KEY[..16]).is_err());
+
+    // 重置保留构造方式
+    let mut pmac = Mac::pmac(&KEY);
+    digest::Mac::update(&mut pmac, b"discarded");
+    digest::Mac::reset(&mut pmac);
+    digest::Mac::update(&mut pmac, &msg);
+    let expected = reference(&CipherCtx::new(&KEY), MacAlgorithm::Pmac, &msg);
+    assert!(pmac.verify_slice(&expected).is_ok());
+}
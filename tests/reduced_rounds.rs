@@ -0,0 +1,206 @@
+use rand::{RngCore, SeedableRng, rngs::StdRng};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use wave_vortex::{
+    BatchBackend, CipherCtx, EncryptWriter, IV_SIZE, Layers, Mac, MacAlgorithm, NONCE_SIZE, ROUNDS,
+    RoundStep, SEGMENT_PREFIX_SIZE, SegmentedWriter, StreamMode, TweakableCtx, decrypt_block_288,
+    encrypt_block_288, encrypt_block_rounds, encrypt_sector, encrypt_stream_with_iv, seal,
+    seal_deterministic, trace_encrypt,
+};
+
+const BACKENDS: [BatchBackend; 4] = [
+    BatchBackend::Portable,
+    BatchBackend::Sse2,
+    BatchBackend::Avx2,
+    BatchBackend::Neon,
+];
+
+// 全部 16 种层组合
+fn all_layer_sets() -> impl Iterator<Item = Layers> {
+    (0..16u8).map(|m| Layers {
+        mds: m & 1 != 0,
+        bit_rotate: m & 2 != 0,
+        stream_fwd: m & 4 != 0,
+        vtx_shuffle: m & 8 != 0,
+    })
+}
+fn random_block(rng: &mut StdRng) -> [u8; 36] {
+    let mut block = [0u8; 36];
+    rng.fill_bytes(&mut block);
+    block
+}
+
+#[test]
+fn full_round_count_matches_new() {
+    let key = [0x42u8; 32];
+    let full = CipherCtx::with_rounds(&key, ROUNDS);
+    let reference = CipherCtx::new(&key);
+    assert_eq!(full.round_count(), ROUNDS);
+    assert_eq!(full.layers(), Layers::ALL);
+    assert_eq!(full.subkeys(), reference.subkeys());
+    let block = [0x5au8; 36];
+    assert_eq!(
+        encrypt_block_288(&full, &block),
+        encrypt_block_288(&reference, &block)
+    );
+}
+
+#[test]
+fn zero_rounds_is_identity() {
+    let ctx = CipherCtx::with_rounds(&[7u8; 32], 0);
+    let block = [0x33u8; 36];
+    assert_eq!(encrypt_block_288(&ctx, &block), block);
+    assert!(ctx.subkeys().is_empty());
+}
+
+#[test]
+#[should_panic]
+fn too_many_rounds_panics() {
+    let _ = CipherCtx::with_rounds(&[0u8; 32], ROUNDS + 1);
+}
+
+// n 轮的输出就是完整密码第 n 轮后的中间状态，子密钥也是前 n 轮的
+#[test]
+fn reduced_rounds_are_prefix_of_full_cipher() {
+    let mut rng = StdRng::seed_from_u64(0x0052_4544);
+    let mut key = [0u8; 32];
+    rng.fill_bytes(&mut key);
+    let block = random_block(&mut rng);
+    let full = CipherCtx::new(&key);
+    let states = encrypt_block_rounds(&full, &block);
+    for n in 1..=ROUNDS {
+        let ctx = CipherCtx::with_rounds(&key, n);
+        assert_eq!(encrypt_block_288(&ctx, &block), states[n - 1], "{n} rounds");
+        assert_eq!(ctx.subkeys()[..], full.subkeys()[..n]);
+        assert_eq!(encrypt_block_rounds(&ctx, &block)[..], states[..n]);
+    }
+}
+
+#[test]
+fn every_round_count_and_layer_set_roundtrips() {
+    let mut rng = StdRng::seed_from_u64(0x004c_4159);
+    let mut key = [0u8; 32];
+    rng.fill_bytes(&mut key);
+    for n in [1, 2, 5, ROUNDS] {
+        for layers in all_layer_sets() {
+            let ctx = CipherCtx::with_rounds(&key, n).with_layers(layers);
+            let block = random_block(&mut rng);
+            let ct = encrypt_block_288(&ctx, &block);
+            assert_eq!(
+                decrypt_block_288(&ctx, &ct),
+                block,
+                "{n} rounds, {layers:?}"
+            );
+        }
+    }
+}
+
+// 关闭的层在跟踪中表现为该步骤前后状态不变
+#[test]
+fn disabled_layers_leave_trace_step_unchanged() {
+    let layers = Layers {
+        bit_rotate: false,
+        vtx_shuffle: false,
+        ..Layers::ALL
+    };
+    let ctx = CipherCtx::with_rounds(&[9u8; 32], 3).with_layers(layers);
+    let trace = trace_encrypt(&ctx, &[0xa5u8; 36]);
+    assert_eq!(trace.len(), 3);
+    for round in &trace {
+        assert_eq!(
+            round.after(RoundStep::BitRotate),
+            round.after(RoundStep::SubCellsMds)
+        );
+        assert_eq!(round.output(), round.after(RoundStep::StreamFwd));
+        assert_ne!(
+            round.after(RoundStep::StreamFwd),
+            round.after(RoundStep::BitRotate)
+        );
+    }
+}
+
+#[test]
+fn available_backends_match_single_block_path() {
+    let mut rng = StdRng::seed_from_u64(0x0042_4154);
+    let mut key = [0u8; 32];
+    rng.fill_bytes(&mut key);
+    for backend in BACKENDS.into_iter().filter(|b| b.is_available()) {
+        for (n, layers) in [1, 7, ROUNDS]
+            .into_iter()
+            .flat_map(|n| all_layer_sets().map(move |l| (n, l)))
+        {
+            let ctx = CipherCtx::with_rounds(&key, n).with_layers(layers);
+            let mut blocks: Vec<[u8; 36]> = (0..65).map(|_| random_block(&mut rng)).collect();
+            let plain = blocks.clone();
+            backend.encrypt_blocks(&ctx, &mut blocks);
+            for (pt, ct) in plain.iter().zip(&blocks) {
+                assert_eq!(
+                    encrypt_block_288(&ctx, pt),
+                    *ct,
+                    "{backend:?}, {n} rounds, {layers:?}"
+                );
+            }
+            backend.decrypt_blocks(&ctx, &mut blocks);
+            assert_eq!(blocks, plain, "{backend:?}, {n} rounds, {layers:?}");
+        }
+    }
+}
+
+// 缩减轮和消融实例只能用于分组层面，交给加密模式时必须拒绝
+#[test]
+fn weakened_contexts_are_refused_by_modes() {
+    let key = [3u8; 32];
+    let weakened = [
+        CipherCtx::with_rounds(&key, ROUNDS - 1),
+        CipherCtx::new(&key).with_layers(Layers {
+            mds: false,
+            ..Layers::ALL
+        }),
+    ];
+    for ctx in weakened {
+        assert!(!ctx.is_full_strength());
+        let uses: [&dyn Fn(); 7] = [
+            &|| drop(seal(&ctx, &[0; NONCE_SIZE], b"", b"data")),
+            &|| drop(seal_deterministic(&ctx, b"", b"data")),
+            &|| drop(Mac::with_ctx(ctx.clone(), MacAlgorithm::Cmac)),
+            &|| {
+                drop(EncryptWriter::new(
+                    Vec::new(),
+                    ctx.clone(),
+                    StreamMode::Segmented,
+                ))
+            },
+            &|| {
+                drop(SegmentedWriter::with_prefix(
+                    Vec::new(),
+                    ctx.clone(),
+                    &[0; SEGMENT_PREFIX_SIZE],
+                    b"",
+                ))
+            },
+            &|| {
+                let _ = TweakableCtx::from_ctx(ctx.clone());
+            },
+            &|| {
+                drop(encrypt_stream_with_iv(
+                    &mut &b"data"[..],
+                    &mut Vec::new(),
+                    &ctx,
+                    &[0; IV_SIZE],
+                ))
+            },
+        ];
+        for (i, f) in uses.iter().enumerate() {
+            assert!(catch_unwind(AssertUnwindSafe(f)).is_err(), "use {i}");
+        }
+    }
+    assert!(CipherCtx::with_rounds(&key, ROUNDS).is_full_strength());
+}
+
+// 扇区加密经由 TweakableCtx，同样不接受缩减轮上下文
+#[test]
+#[should_panic(expected = "reduced-round")]
+fn reduced_round_sector_encryption_panics() {
+    let ctx = TweakableCtx::from_ctx(CipherCtx::with_rounds(&[3u8; 32], 4));
+    let mut sector = [0u8; 512];
+    let _ = encrypt_sector(&ctx, 0, &mut sector);
+}
@@ -0,0 +1,135 @@
+mod common;
+
+use common::sample;
+use rand::{RngCore, SeedableRng, rngs::StdRng};
+use std::io::Cursor;
+use wave_vortex::{
+    CipherCtx, FileHeader, IV_SIZE, Kdf, NONCE_SIZE, StreamMode, StreamOptions, decrypt_stream,
+    decrypt_stream_with_ctx, encrypt_stream_with_header, encrypt_stream_with_iv,
+    encrypt_stream_with_rng, open_stream_with_ctx, seal_stream_with_nonce,
+};
+
+const PASSWORD: &[u8] = b"correct horse";
+const MODES: [StreamMode; 3] = [StreamMode::Cbc, StreamMode::Eax, StreamMode::Segmented];
+
+fn options(mode: StreamMode) -> StreamOptions {
+    StreamOptions {
+        mode,
+        kdf: Kdf::Pbkdf2Sha256 { iterations: 1000 },
+        threads: 1,
+    }
+}
+fn seal_with_seed(mode: StreamMode, seed: u64, data: &[u8]) -> Vec<u8> {
+    let mut sealed = Vec::new();
+    let mut rng = StdRng::seed_from_u64(seed);
+    encrypt_stream_with_rng(
+        &mut Cursor::new(data),
+        &mut sealed,
+        PASSWORD,
+        &options(mode),
+        &mut rng,
+    )
+    .unwrap();
+    sealed
+}
+
+// 固定种子得到相同输出，换种子输出不同，且都能正常解密
+#[test]
+fn seeded_rng_gives_reproducible_files() {
+    let data = sample(300);
+    for mode in MODES {
+        let sealed = seal_with_seed(mode, 1, &data);
+        assert_eq!(seal_with_seed(mode, 1, &data), sealed, "{mode:?}");
+        assert_ne!(seal_with_seed(mode, 2, &data), sealed, "{mode:?}");
+        let mut opened = Vec::new();
+        decrypt_stream(&mut Cursor::new(&sealed), &mut opened, PASSWORD).unwrap();
+        assert_eq!(opened, data, "{mode:?}");
+    }
+}
+
+// 随机数源按先盐后 nonce 的顺序取值，与显式文件头的结果一致
+#[test]
+fn rng_variant_matches_explicit_header() {
+    let data = sample(100);
+    for mode in MODES {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut salt = vec![0u8; 16];
+        rng.fill_bytes(&mut salt);
+        let mut nonce = vec![0u8; mode.nonce_size()];
+        rng.fill_bytes(&mut nonce);
+        let header = FileHeader {
+            kdf: Kdf::Pbkdf2Sha256 { iterations: 1000 },
+            salt,
+            mode,
+            nonce,
+        };
+        let mut sealed = Vec::new();
+        encrypt_stream_with_header(&mut Cursor::new(&data), &mut sealed, PASSWORD, &header, 0)
+            .unwrap();
+        assert_eq!(sealed, seal_with_seed(mode, 7, &data), "{mode:?}");
+        assert_eq!(sealed[..header.to_bytes().len()], header.to_bytes());
+    }
+}
+
+#[test]
+fn invalid_headers_are_rejected_before_writing() {
+    let bad = [
+        FileHeader {
+            kdf: Kdf::Pbkdf2Sha256 { iterations: 1000 },
+            salt: vec![1; 16],
+            mode: StreamMode::Eax,
+            nonce: vec![0; NONCE_SIZE - 1],
+        },
+        FileHeader {
+            kdf: Kdf::Pbkdf2Sha256 { iterations: 1000 },
+            salt: Vec::new(),
+            mode: StreamMode::Cbc,
+            nonce: vec![0; IV_SIZE],
+        },
+    ];
+    for header in &bad {
+        let mut sealed = Vec::new();
+        let err =
+            encrypt_stream_with_header(&mut Cursor::new(b"x"), &mut sealed, PASSWORD, header, 1)
+                .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(sealed.is_empty());
+    }
+}
+
+#[test]
+fn raw_key_variants_accept_explicit_iv_and_nonce() {
+    let ctx = CipherCtx::new(&[0x31; 32]);
+    let data = sample(200);
+
+    let seal_cbc = || {
+        let mut sealed = Vec::new();
+        encrypt_stream_with_iv(&mut Cursor::new(&data), &mut sealed, &ctx, &[5; IV_SIZE]).unwrap();
+        sealed
+    };
+    let sealed = seal_cbc();
+    assert_eq!(sealed, seal_cbc());
+    assert_eq!(sealed[..IV_SIZE], [5; IV_SIZE]);
+    let mut opened = Vec::new();
+    decrypt_stream_with_ctx(&mut Cursor::new(&sealed), &mut opened, &ctx).unwrap();
+    assert_eq!(opened, data);
+
+    let seal_eax = || {
+        let mut sealed = Vec::new();
+        seal_stream_with_nonce(
+            &mut Cursor::new(&data),
+            &mut sealed,
+            &ctx,
+            &[6; NONCE_SIZE],
+            b"ad",
+        )
+        .unwrap();
+        sealed
+    };
+    let sealed = seal_eax();
+    assert_eq!(sealed, seal_eax());
+    assert_eq!(sealed[..NONCE_SIZE], [6; NONCE_SIZE]);
+    let mut opened = Vec::new();
+    open_stream_with_ctx(&mut Cursor::new(&sealed), &mut opened, &ctx, b"ad").unwrap();
+    assert_eq!(opened, data);
+}
@@ -0,0 +1,9 @@
+use wave_vortex::{INV_SBOX, SBOX};
+
+#[test]
+fn inv_sbox_inverts_sbox() {
+    for x in 0..512u16 {
+        assert_eq!(INV_SBOX[SBOX[x as usize] as usize], x, "x = {x:#05x}");
+        assert_eq!(SBOX[INV_SBOX[x as usize] as usize], x, "y = {x:#05x}");
+    }
+}
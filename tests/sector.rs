@@ -0,0 +1,89 @@
+mod common;
+
+use common::{sample, tweakable_ctx};
+use wave_vortex::{decrypt_sector, encrypt_block_tweak, encrypt_sector};
+
+// 512 和 4096 都不是 36 的倍数，会走密文挪用；504 与 36 是整块的情况
+#[test]
+fn sectors_roundtrip_and_preserve_length() {
+    let ctx = tweakable_ctx();
+    for len in [36, 37, 71, 504, 512, 4096] {
+        for sector_no in [0, 1, u64::MAX] {
+            let data = sample(len);
+            let mut sector = data.clone();
+            encrypt_sector(&ctx, sector_no, &mut sector).unwrap();
+            assert_eq!(sector.len(), len);
+            assert_ne!(sector, data);
+            decrypt_sector(&ctx, sector_no, &mut sector).unwrap();
+            assert_eq!(sector, data, "{len} {sector_no}");
+        }
+    }
+}
+
+// 整块扇区的第 j 块就是以 (扇区号, j) 为 tweak 的单块加密
+#[test]
+fn full_block_sectors_use_position_tweaks() {
+    let ctx = tweakable_ctx();
+    let data = sample(504);
+    let mut sector = data.clone();
+    encrypt_sector(&ctx, 9, &mut sector).unwrap();
+    for (j, (pt, ct)) in data.chunks(36).zip(sector.chunks(36)).enumerate() {
+        let mut tweak = [0u8; 16];
+        tweak[..8].copy_from_slice(&9u64.to_le_bytes());
+        tweak[8..].copy_from_slice(&(j as u64).to_le_bytes());
+        assert_eq!(
+            encrypt_block_tweak(&ctx, &tweak, pt.try_into().unwrap()),
+            ct,
+            "block {j}"
+        );
+    }
+}
+
+// 同一内容放在不同扇区或同一扇区的不同位置，密文互不相同；扇区可单独解密
+#[test]
+fn identical_data_differs_across_sectors_and_positions() {
+    let ctx = tweakable_ctx();
+    let data = vec![0u8; 4096];
+    let image: Vec<Vec<u8>> = (0..4)
+        .map(|n| {
+            let mut sector = data.clone();
+            encrypt_sector(&ctx, n, &mut sector).unwrap();
+            sector
+        })
+        .collect();
+    for a in 0..4 {
+        for b in a + 1..4 {
+            assert_ne!(image[a], image[b]);
+        }
+        let blocks: Vec<&[u8]> = image[a].chunks_exact(36).collect();
+        for j in 1..blocks.len() {
+            assert_ne!(blocks[j - 1], blocks[j]);
+        }
+    }
+    let mut third = image[2].clone();
+    decrypt_sector(&ctx, 2, &mut third).unwrap();
+    assert_eq!(third, data);
+    let mut wrong = image[2].clone();
+    decrypt_sector(&ctx, 3, &mut wrong).unwrap();
+    assert_ne!(wrong, data);
+}
+
+// 修改残块只影响挪用涉及的最后两块密文，前面的块保持不变
+#[test]
+fn stealing_is_local_to_the_tail() {
+    let ctx = tweakable_ctx();
+    let data = sample(512);
+    let mut a = data.clone();
+    encrypt_sector(&ctx, 5, &mut a).unwrap();
+    let mut changed = data.clone();
+    changed[511] ^= 1;
+    let mut b = changed.clone();
+    encrypt_sector(&ctx, 5, &mut b).unwrap();
+    let tail = (512 / 36 - 1) * 36;
+    assert_eq!(a[..tail], b[..tail]);
+    assert_ne!(a[tail..], b[tail..]);
+
+    let mut short = vec![0u8; 35];
+    assert!(encrypt_sector(&ctx, 0, &mut short).is_err());
+    assert!(decrypt_sector(&ctx, 0, &mut short).is_err());
+}
@@ -0,0 +1,156 @@
+mod common;
+
+use common::{ctx, sample};
+use std::io::{Cursor, Read, Write};
+use wave_vortex::{
+    CipherCtx, Kdf, SEGMENT_PREFIX_SIZE, SEGMENT_SIZE, SegmentedReader, SegmentedWriter,
+    StreamMode, StreamOptions, TAG_SIZE, decrypt_stream, encrypt_stream_with_options,
+    open_segmented_with_ctx, seal_segmented_with_prefix,
+};
+
+const PREFIX: [u8; SEGMENT_PREFIX_SIZE] = [7; SEGMENT_PREFIX_SIZE];
+const SEALED_SEGMENT: usize = SEGMENT_SIZE + TAG_SIZE;
+
+fn seal(ctx: &CipherCtx, data: &[u8], threads: usize) -> Vec<u8> {
+    let mut sealed = Vec::new();
+    seal_segmented_with_prefix(
+        &mut Cursor::new(data),
+        &mut sealed,
+        ctx,
+        &PREFIX,
+        b"ad",
+        threads,
+    )
+    .unwrap();
+    sealed
+}
+fn open(ctx: &CipherCtx, sealed: &[u8], threads: usize) -> std::io::Result<Vec<u8>> {
+    let mut opened = Vec::new();
+    open_segmented_with_ctx(&mut Cursor::new(sealed), &mut opened, ctx, b"ad", threads)?;
+    Ok(opened)
+}
+
+#[test]
+fn roundtrip_on_segment_boundaries() {
+    let ctx = ctx();
+    for len in [0, 1, SEGMENT_SIZE - 1, SEGMENT_SIZE, SEGMENT_SIZE + 1] {
+        let data = sample(len);
+        let sealed = seal(&ctx, &data, 2);
+        let segments = len.div_ceil(SEGMENT_SIZE).max(1);
+        assert_eq!(
+            sealed.len(),
+            SEGMENT_PREFIX_SIZE + len + segments * TAG_SIZE
+        );
+        assert_eq!(open(&ctx, &sealed, 3).unwrap(), data);
+    }
+}
+
+// 输出只取决于输入和前缀，与加解密两端的线程数都无关
+#[test]
+fn output_is_independent_of_thread_count() {
+    let ctx = ctx();
+    let data = sample(5 * SEGMENT_SIZE / 2);
+    let reference = seal(&ctx, &data, 1);
+    for threads in [2, 3, 0] {
+        assert_eq!(seal(&ctx, &data, threads), reference);
+    }
+    for threads in [1, 2, 0] {
+        assert_eq!(open(&ctx, &reference, threads).unwrap(), data);
+    }
+}
+
+#[test]
+fn detects_reordered_truncated_and_extended_segments() {
+    let ctx = ctx();
+    let sealed = seal(&ctx, &sample(5 * SEGMENT_SIZE / 2), 2);
+    let body = SEGMENT_PREFIX_SIZE;
+    let seg = |i: usize| &sealed[body + i * SEALED_SEGMENT..body + (i + 1) * SEALED_SEGMENT];
+
+    let mut swapped = sealed[..body].to_vec();
+    swapped.extend_from_slice(seg(1));
+    swapped.extend_from_slice(seg(0));
+    swapped.extend_from_slice(&sealed[body + 2 * SEALED_SEGMENT..]);
+    assert!(open(&ctx, &swapped, 2).is_err());
+
+    // 在段边界截断：剩下的最后一段并未带末段标志
+    let truncated = &sealed[..body + 2 * SEALED_SEGMENT];
+    assert!(open(&ctx, truncated, 2).is_err());
+    assert!(open(&ctx, &sealed[..body], 2).is_err());
+    assert!(open(&ctx, &sealed[..sealed.len() - 1], 2).is_err());
+
+    let mut extended = sealed.clone();
+    extended.extend_from_slice(&sealed[sealed.len() - TAG_SIZE..]);
+    assert!(open(&ctx, &extended, 2).is_err());
+
+    let mut flipped = sealed.clone();
+    flipped[body + SEALED_SEGMENT + 5] ^= 1;
+    assert!(open(&ctx, &flipped, 2).is_err());
+}
+
+#[test]
+fn password_files_record_segmented_mode() {
+    let data = sample(SEGMENT_SIZE + 100);
+    let options = StreamOptions {
+        mode: StreamMode::Segmented,
+        kdf: Kdf::Pbkdf2Sha256 { iterations: 1000 },
+        threads: 2,
+    };
+    let mut sealed = Vec::new();
+    encrypt_stream_with_options(&mut Cursor::new(&data), &mut sealed, b"pw", &options).unwrap();
+    let mut opened = Vec::new();
+    decrypt_stream(&mut Cursor::new(&sealed), &mut opened, b"pw").unwrap();
+    assert_eq!(opened, data);
+
+    // 文件头作为每段的关联数据：改动文件头中的 nonce 前缀会使认证失败
+    let nonce_pos = sealed.len() - (data.len() + 2 * TAG_SIZE) - 1;
+    sealed[nonce_pos] ^= 1;
+    assert!(decrypt_stream(&mut Cursor::new(&sealed), &mut Vec::new(), b"pw").is_err());
+}
+
+// 在线适配器与并行函数产生同一格式，可以互相解密
+#[test]
+fn adapters_match_parallel_format() {
+    let ctx = ctx();
+    let data = sample(2 * SEGMENT_SIZE + 17);
+    let sealed = seal(&ctx, &data, 2);
+
+    let mut writer = SegmentedWriter::with_prefix(Vec::new(), ctx.clone(), &PREFIX, b"ad");
+    // 不规则的写入长度，覆盖恰好写满一段的情况
+    for chunk in data.chunks(SEGMENT_SIZE / 3 + 1) {
+        writer.write_all(chunk).unwrap();
+    }
+    assert_eq!(writer.finish().unwrap(), sealed);
+
+    let mut reader = SegmentedReader::new(Cursor::new(&sealed), ctx.clone(), b"ad");
+    let mut opened = Vec::new();
+    reader.read_to_end(&mut opened).unwrap();
+    assert_eq!(opened, data);
+
+    let empty = SegmentedWriter::with_prefix(Vec::new(), ctx.clone(), &PREFIX, b"ad")
+        .finish()
+        .unwrap();
+    assert_eq!(empty, seal(&ctx, b"", 1));
+}
+
+// 截断或篡改时只交出之前已认证的段，随后持续报错，不会表现为正常结束
+#[test]
+fn reader_releases_only_authenticated_segments() {
+    let ctx = ctx();
+    let data = sample(2 * SEGMENT_SIZE + 17);
+    let sealed = seal(&ctx, &data, 2);
+    let truncated = &sealed[..SEGMENT_PREFIX_SIZE + 2 * SEALED_SEGMENT];
+
+    let mut reader = SegmentedReader::new(Cursor::new(truncated), ctx.clone(), b"ad");
+    let mut first = vec![0u8; SEGMENT_SIZE];
+    reader.read_exact(&mut first).unwrap();
+    assert_eq!(first, data[..SEGMENT_SIZE]);
+    let mut rest = Vec::new();
+    assert!(reader.read_to_end(&mut rest).is_err());
+    assert!(rest.is_empty());
+    assert!(reader.read(&mut [0u8; 16]).is_err());
+
+    let mut extended = sealed.clone();
+    extended.extend_from_slice(&sealed[SEGMENT_PREFIX_SIZE..SEGMENT_PREFIX_SIZE + SEALED_SEGMENT]);
+    let mut reader = SegmentedReader::new(Cursor::new(&extended), ctx, b"ad");
+    assert!(reader.read_to_end(&mut Vec::new()).is_err());
+}
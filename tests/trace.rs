@@ -0,0 +1,79 @@
+mod common;
+
+use common::{ctx, pack, sample, unpack};
+use wave_vortex::{RoundStep, encrypt_block_288, encrypt_block_rounds, format_grid, trace_encrypt};
+
+fn block() -> [u8; 36] {
+    sample(36).try_into().unwrap()
+}
+fn popcount(cells: &[u16; 32]) -> u32 {
+    cells.iter().map(|c| c.count_ones()).sum()
+}
+
+#[test]
+fn trace_is_consistent_with_encryption() {
+    let ctx = ctx();
+    let pt = block();
+    let trace = trace_encrypt(&ctx, &pt);
+    let rounds = encrypt_block_rounds(&ctx, &pt);
+    assert_eq!(trace.len(), rounds.len());
+    assert_eq!(trace[0].input, unpack(&pt));
+    for (i, (t, state)) in trace.iter().zip(&rounds).enumerate() {
+        assert_eq!(t.round, i + 1);
+        assert_eq!(pack(t.output()), *state, "round {}", t.round);
+        if i > 0 {
+            assert_eq!(t.input, *trace[i - 1].output());
+        }
+    }
+    assert_eq!(
+        pack(trace.last().unwrap().output()),
+        encrypt_block_288(&ctx, &pt)
+    );
+}
+
+// 按各步骤的定义逐一核对相邻两步之间的关系
+#[test]
+fn steps_follow_their_definitions() {
+    let ctx = ctx();
+    let subkeys = ctx.subkeys();
+    for t in trace_encrypt(&ctx, &block()) {
+        let mask = unpack(&subkeys[t.round - 1].mask);
+        let xored: [u16; 32] = std::array::from_fn(|i| t.input[i] ^ mask[i]);
+        assert_eq!(*t.after(RoundStep::SubKeyXor), xored);
+
+        let mixed = t.after(RoundStep::SubCellsMds);
+        let rotated: [u16; 32] =
+            std::array::from_fn(|i| ((mixed[i] << 1) | (mixed[i] >> 8)) & 0x1ff);
+        assert_eq!(*t.after(RoundStep::BitRotate), rotated);
+
+        // StreamFwd 只移动比特；VtxShuffle 只平移整个 cell
+        let streamed = t.after(RoundStep::StreamFwd);
+        assert_eq!(popcount(streamed), popcount(&rotated));
+        let (mut a, mut b) = (*streamed, *t.output());
+        a.sort_unstable();
+        b.sort_unstable();
+        assert_eq!(a, b);
+        assert!(t.steps.iter().flatten().all(|&c| c <= 0x1ff));
+    }
+}
+
+#[test]
+fn grids_print_four_rows_of_eight_cells() {
+    let cells: [u16; 32] = std::array::from_fn(|i| (i as u16) * 16);
+    let grid = format_grid(&cells);
+    let rows: Vec<Vec<&str>> = grid
+        .lines()
+        .map(|l| l.split_whitespace().collect())
+        .collect();
+    assert_eq!(rows.len(), 4);
+    assert!(rows.iter().all(|r| r.len() == 8));
+    assert_eq!(rows[0][1], "010");
+    assert_eq!(rows[3][7], "1f0");
+
+    let text = trace_encrypt(&ctx(), &block())[0].to_string();
+    assert!(text.starts_with("Round 1\n"));
+    for step in RoundStep::ALL {
+        assert!(text.contains(&format!("  {step}\n")), "{step}");
+    }
+    assert_eq!(text.lines().count(), 1 + 6 * 5);
+}
@@ -0,0 +1,204 @@
+mod common;
+
+use common::pack;
+use rand::{Rng, RngCore, SeedableRng, rngs::StdRng};
+use wave_vortex::{
+    CipherCtx, INV_SBOX, Layers, RoundStep, SBOX, TrailModel, differential_model,
+    mds_branch_number, trace_encrypt,
+};
+
+fn ablations() -> [Layers; 5] {
+    [
+        Layers::ALL,
+        Layers {
+            mds: false,
+            ..Layers::ALL
+        },
+        Layers {
+            bit_rotate: false,
+            ..Layers::ALL
+        },
+        Layers {
+            stream_fwd: false,
+            ..Layers::ALL
+        },
+        Layers {
+            vtx_shuffle: false,
+            ..Layers::ALL
+        },
+    ]
+}
+
+// 由一对明文的实际加密过程给出模型变量的取值
+fn actual_trail(model: &TrailModel, ctx: &CipherCtx, a: &[u8; 36], b: &[u8; 36]) -> Vec<bool> {
+    let mut values = vec![false; model.num_variables()];
+    let mut set = |name: String, value: bool| {
+        if let Some(v) = model.variable(&name) {
+            values[v] = value;
+        }
+    };
+    for (r, (ta, tb)) in trace_encrypt(ctx, a)
+        .iter()
+        .zip(trace_encrypt(ctx, b))
+        .enumerate()
+    {
+        let dx: Vec<u16> = (0..32)
+            .map(|i| ta.after(RoundStep::SubKeyXor)[i] ^ tb.after(RoundStep::SubKeyXor)[i])
+            .collect();
+        let dy: Vec<u16> = (0..32)
+            .map(|i| ta.after(RoundStep::SubCellsMds)[i] ^ tb.after(RoundStep::SubCellsMds)[i])
+            .collect();
+        for i in 0..32 {
+            set(format!("x_{r}_{i}"), dx[i] != 0);
+            set(format!("y_{r}_{i}"), dy[i] != 0);
+            for k in 0..9 {
+                set(format!("b_{r}_{i}_{k}"), dy[i] >> k & 1 != 0);
+            }
+        }
+        for c in 0..8 {
+            let active = (0..4).any(|row| dx[row * 8 + c] != 0 || dy[row * 8 + c] != 0);
+            set(format!("d_{r}_{c}"), active);
+        }
+    }
+    values
+}
+
+// circ(1, 2, 4, 8) 的前三行在后两列上成比例 (4:8 = 2:4 = 1:2)，
+// 所以 (0, 0, 2, 1) 只产生一个非零输出，分支数为 3 而不是 MDS 的 5
+#[test]
+fn mds_branch_number_is_three() {
+    assert_eq!(mds_branch_number(), 3);
+
+    let mds_only = Layers {
+        mds: true,
+        bit_rotate: false,
+        stream_fwd: false,
+        vtx_shuffle: false,
+    };
+    let ctx = CipherCtx::with_rounds(&[5u8; 32], 1).with_layers(mds_only);
+    let a = [0x3cu8; 36];
+    let sa = *trace_encrypt(&ctx, &a)[0].after(RoundStep::SubKeyXor);
+    // 让第 0 列的 S 盒输出差分为 (0, 0, 2, 1)
+    let mut delta = [0u16; 32];
+    for (row, d) in [(2, 2u16), (3, 1)] {
+        let i = row * 8;
+        delta[i] = sa[i] ^ INV_SBOX[(SBOX[sa[i] as usize] ^ d) as usize];
+    }
+    let mut b = a;
+    for (x, d) in b.iter_mut().zip(pack(&delta)) {
+        *x ^= d;
+    }
+    let (ta, tb) = (&trace_encrypt(&ctx, &a)[0], &trace_encrypt(&ctx, &b)[0]);
+    let active = |step| {
+        (0..32)
+            .filter(|&i| ta.after(step)[i] != tb.after(step)[i])
+            .count()
+    };
+    assert_eq!(active(RoundStep::SubKeyXor), 2);
+    assert_eq!(active(RoundStep::SubCellsMds), 1);
+}
+
+// 模型是松弛：任何真实的差分传播都必须是可行解，目标值等于实际活跃 S 盒数
+#[test]
+fn actual_differences_satisfy_model() {
+    let mut rng = StdRng::seed_from_u64(0x0054_5241);
+    for layers in ablations() {
+        for rounds in [1, 2, 3, 5] {
+            let mut key = [0u8; 32];
+            rng.fill_bytes(&mut key);
+            let ctx = CipherCtx::with_rounds(&key, rounds).with_layers(layers);
+            let model = differential_model(&ctx);
+            for _ in 0..8 {
+                let mut a = [0u8; 36];
+                rng.fill_bytes(&mut a);
+                let mut b = a;
+                b[rng.gen_range(0..36)] ^= 1 << rng.gen_range(0..8);
+                let values = actual_trail(&model, &ctx, &a, &b);
+                assert!(model.is_feasible(&values), "{rounds} rounds, {layers:?}");
+                let active: usize = trace_encrypt(&ctx, &a)
+                    .iter()
+                    .zip(trace_encrypt(&ctx, &b))
+                    .map(|(ta, tb)| {
+                        let (sa, sb) = (
+                            ta.after(RoundStep::SubKeyXor),
+                            tb.after(RoundStep::SubKeyXor),
+                        );
+                        (0..32).filter(|&i| sa[i] != sb[i]).count()
+                    })
+                    .sum();
+                assert_eq!(model.objective_value(&values), active);
+            }
+        }
+    }
+}
+
+#[test]
+fn zero_difference_is_excluded() {
+    let ctx = CipherCtx::with_rounds(&[1u8; 32], 2);
+    let model = differential_model(&ctx);
+    assert!(!model.is_feasible(&vec![false; model.num_variables()]));
+}
+
+// 分支数为 3：单个活跃 S 盒所在列至少还有 2 个活跃的 MDS 输出
+#[test]
+fn mds_branch_constraint_is_enforced() {
+    let ctx = CipherCtx::with_rounds(&[2u8; 32], 2);
+    let model = differential_model(&ctx);
+    let mut values = vec![false; model.num_variables()];
+    values[model.variable("x_0_0").unwrap()] = true;
+    values[model.variable("d_0_0").unwrap()] = true;
+    values[model.variable("y_0_0").unwrap()] = true;
+    values[model.variable("b_0_0_0").unwrap()] = true;
+    assert!(!model.is_feasible(&values));
+
+    let ctx = ctx.with_layers(Layers {
+        mds: false,
+        ..Layers::ALL
+    });
+    let model = differential_model(&ctx);
+    assert!(model.variable("y_0_0").is_none());
+    assert!(model.variable("d_0_0").is_none());
+}
+
+// 分支数为 3 时 3 个活跃输入已满足分支约束，还必须要求 MDS 输出非零，
+// 否则模型允许差分在一轮内消失，多轮最优值停在 3
+#[test]
+fn active_column_keeps_an_active_output() {
+    let ctx = CipherCtx::with_rounds(&[4u8; 32], 2);
+    let model = differential_model(&ctx);
+    let mut values = vec![false; model.num_variables()];
+    for name in ["x_0_0", "x_0_8", "x_0_16", "d_0_0"] {
+        values[model.variable(name).unwrap()] = true;
+    }
+    assert!(!model.is_feasible(&values));
+}
+
+#[test]
+fn lp_output_is_well_formed() {
+    let ctx = CipherCtx::with_rounds(&[3u8; 32], 3);
+    let model = differential_model(&ctx);
+    let mut out = Vec::new();
+    model.write_lp(&mut out).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    assert!(text.starts_with("\\ WVX differential trail model: 3 rounds"));
+    for section in ["Minimize", "Subject To", "Binary"] {
+        assert_eq!(
+            text.lines().filter(|l| *l == section).count(),
+            1,
+            "{section}"
+        );
+    }
+    assert!(text.ends_with("\nEnd\n"));
+    let constraints = text.lines().filter(|l| l.starts_with(" c")).count();
+    assert_eq!(constraints, model.num_constraints());
+    // CPLEX LP 限制每行最多 510 个字符
+    assert!(text.lines().all(|l| l.len() <= 255));
+    let binaries: usize = text
+        .lines()
+        .skip_while(|l| *l != "Binary")
+        .skip(1)
+        .take_while(|l| *l != "End")
+        .map(|l| l.split_whitespace().count())
+        .sum();
+    assert_eq!(binaries, model.num_variables());
+}